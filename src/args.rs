@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 use crate::data::theme::ThemeType;
@@ -9,14 +11,77 @@ pub struct OpenMCArgs {
     pub theme: Option<ThemeType>,
     #[clap(long, default_value = "false")]
     pub no_gui: bool,
+    /// Passphrase of the encrypted file with the tokens of the accounts
+    #[clap(long, env = "OPENMC_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+    /// Application (client) id of the Azure app used by the Microsoft login
+    #[clap(long, env = "OPENMC_MS_CLIENT_ID")]
+    pub ms_client_id: Option<String>,
+    /// Server with all the services of the login, used to test it with a local server
+    #[clap(long, env = "OPENMC_AUTH_SERVER", hide = true)]
+    pub auth_server: Option<String>,
     #[clap(subcommand)]
     pub command: Option<OpenMCommands>,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum OpenMCommands {
+    /// Launch an instance without open the launcher window
     Launch {
         #[arg(short, long)]
         instance: String,
     },
+    /// List all instances
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Create a new instance
+    Create {
+        #[arg(short, long)]
+        name: String,
+        /// Version id on the launcher manifest, ex: 1.19.3
+        #[arg(short, long)]
+        version: String,
+        /// Path to a png file, by default uses the launcher icon
+        #[arg(short, long)]
+        icon: Option<String>,
+    },
+    /// Remove an instance and all of its files
+    Remove {
+        #[arg(short, long)]
+        instance: String,
+    },
+    /// Change the name of an instance
+    Rename {
+        #[arg(short, long)]
+        instance: String,
+        #[arg(short, long)]
+        name: String,
+    },
+    /// Show the information of an instance
+    Show {
+        #[arg(short, long)]
+        instance: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the hashes of the files of an instance and download again the broken ones
+    Verify {
+        #[arg(short, long)]
+        instance: String,
+    },
+    /// Manage the java runtimes
+    Java {
+        #[command(subcommand)]
+        command: JavaCommands,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum JavaCommands {
+    /// List the java runtimes found on this machine
+    List,
+    /// Install a runtime from a local .zip, .tar.gz or .tar.xz archive
+    Install { archive: PathBuf },
 }
@@ -0,0 +1,479 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::settings::{AccountOrigin, UserSession};
+
+use super::AuthEndpoints;
+
+static SCOPE: &str = "XboxLive.signin offline_access";
+static DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Code that the user types on the page of Microsoft
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MicrosoftToken {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthError {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct XboxToken {
+    token: String,
+    display_claims: Value,
+}
+
+impl XboxToken {
+    /// User hash, needed by the next steps of the chain
+    fn uhs(&self) -> Result<String> {
+        self.display_claims
+            .pointer("/xui/0/uhs")
+            .and_then(|u| u.as_str())
+            .map(|u| u.to_string())
+            .ok_or_else(|| anyhow!("The Xbox token has no user hash"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MinecraftToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct MinecraftProfile {
+    id: String,
+    name: String,
+}
+
+/// Steps of the login, used to show the progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginStep {
+    WaitingUser,
+    XboxLive,
+    Xsts,
+    Minecraft,
+    Profile,
+}
+
+impl ToString for LoginStep {
+    fn to_string(&self) -> String {
+        match self {
+            LoginStep::WaitingUser => "Waiting for the code".to_string(),
+            LoginStep::XboxLive => "Login on Xbox Live".to_string(),
+            LoginStep::Xsts => "Authorizing with Xbox".to_string(),
+            LoginStep::Minecraft => "Login on Minecraft".to_string(),
+            LoginStep::Profile => "Loading the profile".to_string(),
+        }
+    }
+}
+
+/// The status errors of the services also carry a json body
+fn read_error(e: ureq::Error) -> anyhow::Error {
+    match e {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            anyhow!("The server responded {code}: {body}")
+        }
+        e => anyhow!("Cannot connect: {e}"),
+    }
+}
+
+pub fn request_device_code(endpoints: &AuthEndpoints) -> Result<DeviceCode> {
+    if endpoints.client_id.is_empty() {
+        return Err(anyhow!(
+            "There is no client id for the Microsoft login, set it with OPENMC_MS_CLIENT_ID"
+        ));
+    }
+    let code = ureq::post(&endpoints.device_code)
+        .send_form(&[("client_id", &endpoints.client_id), ("scope", SCOPE)])
+        .map_err(read_error)?
+        .into_json::<DeviceCode>()?;
+    debug!("Device code requested, expires in {}s", code.expires_in);
+    Ok(code)
+}
+
+/// New tokens of Microsoft without ask the user
+pub fn refresh_microsoft(endpoints: &AuthEndpoints, refresh_token: &str) -> Result<MicrosoftToken> {
+    Ok(ureq::post(&endpoints.token)
+        .send_form(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", &endpoints.client_id),
+            ("refresh_token", refresh_token),
+            ("scope", SCOPE),
+        ])
+        .map_err(read_error)?
+        .into_json()?)
+}
+
+/// Wait until the user enters the code, stops when `cancel` is set
+pub fn poll_device_token(
+    endpoints: &AuthEndpoints,
+    code: &DeviceCode,
+    cancel: Arc<AtomicBool>,
+) -> Result<MicrosoftToken> {
+    let started = Instant::now();
+    let mut interval = Duration::from_secs(code.interval.max(1));
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!("Login cancelled"));
+        }
+        if started.elapsed() > Duration::from_secs(code.expires_in) {
+            return Err(anyhow!("The code expired, try again"));
+        }
+        std::thread::sleep(interval);
+        let res = ureq::post(&endpoints.token).send_form(&[
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("client_id", &endpoints.client_id),
+            ("device_code", &code.device_code),
+        ]);
+        match res {
+            Ok(response) => return Ok(response.into_json::<MicrosoftToken>()?),
+            Err(ureq::Error::Status(_, response)) => {
+                let error = response.into_json::<OAuthError>()?;
+                match error.error.as_str() {
+                    "authorization_pending" => {}
+                    "slow_down" => interval += Duration::from_secs(5),
+                    "authorization_declined" => return Err(anyhow!("The login was declined")),
+                    "expired_token" => return Err(anyhow!("The code expired, try again")),
+                    _ => {
+                        return Err(anyhow!(
+                            "{}",
+                            error.error_description.unwrap_or(error.error)
+                        ))
+                    }
+                }
+            }
+            Err(e) => return Err(read_error(e)),
+        }
+    }
+}
+
+fn xbox_live(endpoints: &AuthEndpoints, ms_token: &str) -> Result<XboxToken> {
+    Ok(ureq::post(&endpoints.xbox_live)
+        .set("Accept", "application/json")
+        .send_json(json!({
+            "Properties": {
+                "AuthMethod": "RPS",
+                "SiteName": "user.auth.xboxlive.com",
+                "RpsTicket": format!("d={ms_token}"),
+            },
+            "RelyingParty": "http://auth.xboxlive.com",
+            "TokenType": "JWT",
+        }))
+        .map_err(read_error)?
+        .into_json()?)
+}
+
+fn xsts(endpoints: &AuthEndpoints, xbl_token: &str) -> Result<XboxToken> {
+    let res = ureq::post(&endpoints.xsts)
+        .set("Accept", "application/json")
+        .send_json(json!({
+            "Properties": {
+                "SandboxId": "RETAIL",
+                "UserTokens": [xbl_token],
+            },
+            "RelyingParty": "rp://api.minecraftservices.com/",
+            "TokenType": "JWT",
+        }));
+    match res {
+        Ok(response) => Ok(response.into_json()?),
+        Err(ureq::Error::Status(401, response)) => {
+            let body = response.into_json::<Value>().unwrap_or_default();
+            let reason = match body.get("XErr").and_then(|e| e.as_u64()) {
+                Some(2148916233) => "The account has no Xbox profile, create one on xbox.com",
+                Some(2148916235) => "Xbox Live is not available on your country",
+                Some(2148916236) | Some(2148916237) => "The account needs adult verification",
+                Some(2148916238) => "The account is a child account, add it to a family",
+                _ => "The account cannot use Xbox Live",
+            };
+            Err(anyhow!(reason))
+        }
+        Err(e) => Err(read_error(e)),
+    }
+}
+
+fn minecraft_login(endpoints: &AuthEndpoints, uhs: &str, xsts: &str) -> Result<MinecraftToken> {
+    Ok(ureq::post(&endpoints.minecraft_login)
+        .send_json(json!({ "identityToken": format!("XBL3.0 x={uhs};{xsts}") }))
+        .map_err(read_error)?
+        .into_json()?)
+}
+
+/// Profile of the token, `None` when the token is rejected
+pub(super) fn minecraft_profile(
+    endpoints: &AuthEndpoints,
+    access_token: &str,
+) -> Result<Option<MinecraftProfile>> {
+    let res = ureq::get(&endpoints.minecraft_profile)
+        .set("Authorization", &format!("Bearer {access_token}"))
+        .call();
+    match res {
+        Ok(response) => Ok(Some(response.into_json()?)),
+        Err(ureq::Error::Status(401, _)) => Ok(None),
+        Err(ureq::Error::Status(404, _)) => Err(anyhow!("The account does not own Minecraft")),
+        Err(e) => Err(read_error(e)),
+    }
+}
+
+/// Xbox Live, XSTS and Minecraft services with the token of Microsoft
+pub fn login_minecraft(
+    endpoints: &AuthEndpoints,
+    ms_token: &MicrosoftToken,
+    on_step: impl Fn(LoginStep),
+) -> Result<UserSession> {
+    on_step(LoginStep::XboxLive);
+    let xbl = xbox_live(endpoints, &ms_token.access_token)?;
+    on_step(LoginStep::Xsts);
+    let xsts = xsts(endpoints, &xbl.token)?;
+    on_step(LoginStep::Minecraft);
+    let mc = minecraft_login(endpoints, &xsts.uhs()?, &xsts.token)?;
+    on_step(LoginStep::Profile);
+    let profile = minecraft_profile(endpoints, &mc.access_token)?
+        .ok_or_else(|| anyhow!("The Minecraft token was rejected"))?;
+    info!("Logged with Microsoft as {}", profile.name);
+    debug!("Minecraft token expires in {}s", mc.expires_in);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    Ok(UserSession {
+        name: profile.name,
+        uuid: profile.id,
+        access_token: mc.access_token,
+        refresh_token: ms_token.refresh_token.clone(),
+        expires_at: Some(now + mc.expires_in),
+        origin: AccountOrigin::Microsoft,
+        ..Default::default()
+    })
+}
+
+/// The whole device code flow, the code is sent to `on_code` to show it to the user
+pub fn login_device_code(
+    endpoints: &AuthEndpoints,
+    cancel: Arc<AtomicBool>,
+    on_code: impl Fn(&DeviceCode),
+    on_step: impl Fn(LoginStep),
+) -> Result<UserSession> {
+    let code = request_device_code(endpoints)?;
+    on_code(&code);
+    on_step(LoginStep::WaitingUser);
+    let ms_token = poll_device_token(endpoints, &code, cancel)?;
+    debug!("Microsoft token received");
+    login_minecraft(endpoints, &ms_token, on_step)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::atomic::AtomicUsize,
+    };
+
+    use super::*;
+
+    struct Request {
+        path: String,
+        headers: Vec<String>,
+    }
+
+    /// Local server with the answers of the services, the endpoints point to it
+    fn stub_server(handler: impl Fn(&Request) -> (u16, Value) + Send + 'static) -> AuthEndpoints {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let path = line.split_whitespace().nth(1).unwrap_or_default();
+                let mut request = Request {
+                    path: path.to_string(),
+                    headers: Vec::new(),
+                };
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    request.headers.push(header.to_string());
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let (status, body) = handler(&request);
+                let body = body.to_string();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        AuthEndpoints {
+            client_id: "test-client".to_string(),
+            ..AuthEndpoints::with_server(&server)
+        }
+    }
+
+    fn xbox_token(token: &str) -> Value {
+        json!({
+            "Token": token,
+            "DisplayClaims": { "xui": [{ "uhs": "test-uhs" }] },
+        })
+    }
+
+    /// Answers of a successful login, the first poll of the token is still pending
+    fn services(polls: Arc<AtomicUsize>) -> impl Fn(&Request) -> (u16, Value) + Send + 'static {
+        move |request| match request.path.as_str() {
+            "/devicecode" => (
+                200,
+                json!({
+                    "device_code": "test-device",
+                    "user_code": "ABCD-1234",
+                    "verification_uri": "https://microsoft.com/devicelogin",
+                    "expires_in": 60,
+                    "interval": 1,
+                    "message": "Enter the code",
+                }),
+            ),
+            "/token" if polls.fetch_add(1, Ordering::SeqCst) == 0 => {
+                (400, json!({ "error": "authorization_pending" }))
+            }
+            "/token" => (
+                200,
+                json!({
+                    "access_token": "ms-access",
+                    "refresh_token": "ms-refresh",
+                    "expires_in": 3600,
+                }),
+            ),
+            "/xbox_live" => (200, xbox_token("xbl-token")),
+            "/xsts" => (200, xbox_token("xsts-token")),
+            "/minecraft_login" => (
+                200,
+                json!({ "access_token": "mc-access", "expires_in": 86400 }),
+            ),
+            "/minecraft_profile"
+                if request
+                    .headers
+                    .iter()
+                    .any(|h| h == "Authorization: Bearer mc-access") =>
+            {
+                (
+                    200,
+                    json!({ "id": "069a79f444e94726a5befca90e38aaf5", "name": "Notch" }),
+                )
+            }
+            "/minecraft_profile" => (401, json!({})),
+            _ => (404, json!({})),
+        }
+    }
+
+    #[test]
+    fn device_code_login() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let endpoints = stub_server(services(polls.clone()));
+        let shown = std::sync::Mutex::new(None);
+        let session = login_device_code(
+            &endpoints,
+            Arc::new(AtomicBool::new(false)),
+            |code| *shown.lock().unwrap() = Some(code.user_code.clone()),
+            |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(shown.lock().unwrap().as_deref(), Some("ABCD-1234"));
+        assert_eq!(polls.load(Ordering::SeqCst), 2);
+        assert_eq!(session.name, "Notch");
+        assert_eq!(session.uuid, "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(session.access_token, "mc-access");
+        assert_eq!(session.refresh_token, "ms-refresh");
+        assert_eq!(session.origin, AccountOrigin::Microsoft);
+        assert!(!session.is_expired());
+    }
+
+    #[test]
+    fn cancelled_login() {
+        let endpoints = stub_server(services(Arc::new(AtomicUsize::new(0))));
+        let res = login_device_code(&endpoints, Arc::new(AtomicBool::new(true)), |_| {}, |_| {});
+        assert_eq!(res.unwrap_err().to_string(), "Login cancelled");
+    }
+
+    #[test]
+    fn rejected_profile_token() {
+        let endpoints = stub_server(services(Arc::new(AtomicUsize::new(0))));
+        assert!(minecraft_profile(&endpoints, "old-token")
+            .unwrap()
+            .is_none());
+        assert!(minecraft_profile(&endpoints, "mc-access")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn child_account_on_xsts() {
+        let endpoints = stub_server(|request| match request.path.as_str() {
+            "/xsts" => (401, json!({ "XErr": 2148916238u64 })),
+            _ => (200, xbox_token("xbl-token")),
+        });
+        let token = MicrosoftToken {
+            access_token: "ms-access".to_string(),
+            refresh_token: "ms-refresh".to_string(),
+            expires_in: 3600,
+        };
+        let err = login_minecraft(&endpoints, &token, |_| {}).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The account is a child account, add it to a family"
+        );
+    }
+
+    #[test]
+    fn missing_client_id() {
+        let endpoints = AuthEndpoints {
+            client_id: String::new(),
+            ..AuthEndpoints::with_server("http://127.0.0.1:9")
+        };
+        assert!(request_device_code(&endpoints).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+mod microsoft;
+mod session;
+
+pub use microsoft::*;
+pub use session::*;
+
+/// Urls of the services used by the login, they can point to a local server for testing.
+/// They are never saved, the defaults of the build are replaced by the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthEndpoints {
+    /// Application (client) id of the Azure app
+    pub client_id: String,
+    pub device_code: String,
+    pub token: String,
+    pub xbox_live: String,
+    pub xsts: String,
+    pub minecraft_login: String,
+    pub minecraft_profile: String,
+}
+
+impl Default for AuthEndpoints {
+    fn default() -> Self {
+        Self {
+            client_id: option_env!("OPENMC_MS_CLIENT_ID")
+                .unwrap_or_default()
+                .to_string(),
+            device_code: "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode"
+                .to_string(),
+            token: "https://login.microsoftonline.com/consumers/oauth2/v2.0/token".to_string(),
+            xbox_live: "https://user.auth.xboxlive.com/user/authenticate".to_string(),
+            xsts: "https://xsts.auth.xboxlive.com/xsts/authorize".to_string(),
+            minecraft_login: "https://api.minecraftservices.com/authentication/login_with_xbox"
+                .to_string(),
+            minecraft_profile: "https://api.minecraftservices.com/minecraft/profile".to_string(),
+        }
+    }
+}
+
+impl AuthEndpoints {
+    /// All the services on the same server
+    pub fn with_server(server: &str) -> Self {
+        let server = server.trim_end_matches('/');
+        Self {
+            device_code: format!("{server}/devicecode"),
+            token: format!("{server}/token"),
+            xbox_live: format!("{server}/xbox_live"),
+            xsts: format!("{server}/xsts"),
+            minecraft_login: format!("{server}/minecraft_login"),
+            minecraft_profile: format!("{server}/minecraft_profile"),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use log::{debug, info, warn};
+
+use crate::settings::{AccountOrigin, UserSession};
+
+use super::{login_minecraft, minecraft_profile, refresh_microsoft, AuthEndpoints};
+
+/// Result of check the token of a session before launch
+#[derive(Clone)]
+pub enum SessionStatus {
+    Valid,
+    /// The token expired and it was renewed, the session must be saved
+    Refreshed(UserSession),
+    /// The token cannot be renewed, the user must log in again
+    Relogin(String),
+    /// The services cannot be reached
+    Failed(String),
+}
+
+/// Validate the token with the services and renew it when is expired
+pub fn check_session(endpoints: &AuthEndpoints, session: &UserSession) -> SessionStatus {
+    if session.origin == AccountOrigin::Local {
+        return SessionStatus::Valid;
+    }
+    if !session.is_expired() {
+        match minecraft_profile(endpoints, &session.access_token) {
+            Ok(Some(_)) => return SessionStatus::Valid,
+            Ok(None) => info!("The token of {} was rejected", session.name),
+            Err(e) => {
+                // without network the game cannot validate the token either,
+                // the expiration says if it is still alive
+                warn!("Cannot validate the token of {}: {e}", session.name);
+                return match session.expires_at {
+                    Some(_) => SessionStatus::Valid,
+                    None => SessionStatus::Failed(e.to_string()),
+                };
+            }
+        }
+    }
+    refresh_session(endpoints, session)
+}
+
+fn refresh_session(endpoints: &AuthEndpoints, session: &UserSession) -> SessionStatus {
+    if session.origin != AccountOrigin::Microsoft || session.refresh_token.is_empty() {
+        return SessionStatus::Relogin(format!("The session of {} expired", session.name));
+    }
+    debug!("Refreshing the token of {}", session.name);
+    let res = refresh_microsoft(endpoints, &session.refresh_token)
+        .and_then(|token| login_minecraft(endpoints, &token, |_| {}));
+    match res {
+        Ok(new_session) => {
+            info!("Token of {} refreshed", session.name);
+            SessionStatus::Refreshed(UserSession {
+                id: session.id.clone(),
+                face_img: session.face_img.clone(),
+                ..new_session
+            })
+        }
+        Err(e) => {
+            warn!("Cannot refresh the token of {}: {e}", session.name);
+            SessionStatus::Relogin(format!("Cannot renew the session of {}", session.name))
+        }
+    }
+}
+
+struct PendingCheck {
+    instance: String,
+    rcv: Receiver<SessionStatus>,
+}
+
+/// Checks of the sessions running on background, one for each launch
+#[derive(Default)]
+pub struct SessionValidator {
+    pending: Vec<PendingCheck>,
+}
+
+impl SessionValidator {
+    pub fn validate(&mut self, instance: &str, session: &UserSession, endpoints: &AuthEndpoints) {
+        if self.is_validating(instance) {
+            return;
+        }
+        let (sender, rcv) = std::sync::mpsc::channel();
+        let (session, endpoints) = (session.clone(), endpoints.clone());
+        std::thread::spawn(move || {
+            let _ = sender.send(check_session(&endpoints, &session));
+        });
+        self.pending.push(PendingCheck {
+            instance: instance.to_string(),
+            rcv,
+        });
+    }
+
+    pub fn is_validating(&self, instance: &str) -> bool {
+        self.pending.iter().any(|p| p.instance == instance)
+    }
+
+    pub fn any_validating(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Returns the instance and the status of each check finished
+    pub fn poll(&mut self) -> Vec<(String, SessionStatus)> {
+        let mut finished = Vec::new();
+        self.pending.retain(|p| match p.rcv.try_recv() {
+            Ok(status) => {
+                finished.push((p.instance.clone(), status));
+                false
+            }
+            Err(TryRecvError::Empty) => true,
+            Err(TryRecvError::Disconnected) => {
+                finished.push((
+                    p.instance.clone(),
+                    SessionStatus::Failed("The validation stopped".to_string()),
+                ));
+                false
+            }
+        });
+        finished
+    }
+}
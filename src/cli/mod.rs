@@ -0,0 +1,247 @@
+mod terminal;
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use log::{debug, error};
+
+use crate::{
+    args::{JavaCommands, OpenMCArgs, OpenMCommands},
+    auth::{check_session, SessionStatus},
+    download_svc::{manifest_versions, repair_files, verify_version, DownloadProgress, FileCheck},
+    java::{discover_runtimes, install_runtime},
+    launch::spawn_instance,
+    settings::{AccountOrigin, LauncherInstance, LauncherSettings},
+};
+
+pub use terminal::*;
+
+/// Run a subcommand without open any window, returns the exit code of the process
+pub fn run_command(cmd: OpenMCommands) -> i32 {
+    let mut cfg = match LauncherSettings::new() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+
+    let res = match cmd {
+        OpenMCommands::Launch { instance } => return launch(&mut cfg, &instance),
+        OpenMCommands::List { json } => list(&cfg, json),
+        OpenMCommands::Create {
+            name,
+            version,
+            icon,
+        } => create(&mut cfg, name, &version, icon),
+        OpenMCommands::Remove { instance } => remove(&mut cfg, instance),
+        OpenMCommands::Rename { instance, name } => cfg.rename_instance(&instance, &name),
+        OpenMCommands::Show { instance, json } => show(&cfg, &instance, json),
+        OpenMCommands::Verify { instance } => verify(&cfg, &instance),
+        OpenMCommands::Java { command } => java(command),
+    };
+
+    match res {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+fn launch(cfg: &mut LauncherSettings, name: &str) -> i32 {
+    let Some(instance) = cfg.instances.iter().find(|i| i.name == name).cloned() else {
+        eprintln!("Instance '{name}' not exists");
+        return 1;
+    };
+    if let Err(e) = validate_session(cfg, &instance) {
+        eprintln!("Cannot launch instance '{name}': {e}");
+        return 1;
+    }
+
+    println!("Launching Instance '{}'", instance.name);
+    let mut child = match spawn_instance(&instance, cfg, false) {
+        Ok(child) => child,
+        Err(e) => {
+            error!("Cannot launch instance '{name}': {e}");
+            eprintln!("Cannot launch instance '{name}': {e}");
+            return 1;
+        }
+    };
+
+    match child.wait() {
+        Ok(status) => {
+            debug!("Instance '{name}' finished with: {status}");
+            status.code().unwrap_or(1)
+        }
+        Err(e) => {
+            eprintln!("Cannot wait for instance '{name}': {e}");
+            1
+        }
+    }
+}
+
+/// Check the token of the account used by the instance and save it when is renewed
+pub(super) fn validate_session(
+    cfg: &mut LauncherSettings,
+    instance: &LauncherInstance,
+) -> Result<(), String> {
+    // the local accounts have no tokens
+    if cfg.session_for(instance)?.origin == AccountOrigin::Local {
+        return Ok(());
+    }
+    if cfg.needs_secrets() {
+        let passphrase = OpenMCArgs::parse().passphrase;
+        cfg.unlock_secrets(passphrase.as_deref())
+            .map_err(|e| format!("Cannot unlock the accounts: {e}"))?;
+    }
+    let session = cfg.session_for(instance)?;
+    match check_session(&cfg.auth, session) {
+        SessionStatus::Valid => Ok(()),
+        SessionStatus::Refreshed(session) => {
+            cfg.update_account(session);
+            Ok(())
+        }
+        SessionStatus::Relogin(reason) => Err(format!("{reason}, log in again from the launcher")),
+        SessionStatus::Failed(e) => Err(format!("Cannot validate the session: {e}")),
+    }
+}
+
+fn find_instance<'a>(
+    cfg: &'a LauncherSettings,
+    name: &str,
+) -> Result<&'a LauncherInstance, String> {
+    cfg.instances
+        .iter()
+        .find(|i| i.name == name)
+        .ok_or_else(|| format!("Instance '{name}' not exists"))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+fn list(cfg: &LauncherSettings, json: bool) -> Result<(), String> {
+    if json {
+        println!("{}", to_json(&cfg.instances)?);
+        return Ok(());
+    }
+    for instance in cfg.instances.iter() {
+        let version = instance
+            .version
+            .as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "No Version".to_string());
+        println!("{:<30} {version}", instance.name);
+    }
+    Ok(())
+}
+
+fn show(cfg: &LauncherSettings, name: &str, json: bool) -> Result<(), String> {
+    let instance = find_instance(cfg, name)?;
+    if json {
+        println!("{}", to_json(instance)?);
+        return Ok(());
+    }
+    println!("Name:       {}", instance.name);
+    println!("Path:       {}", instance.path);
+    if let Some(version) = instance.version.as_ref() {
+        println!("Version:    {}", version.get_version_id());
+        println!("Type:       {}", version.get_version_type());
+    }
+    println!("Java:       {}", instance.java_path);
+    println!("Tags:       {}", instance.tags.join(", "));
+    println!("Downloaded: {}", instance.downloaded);
+    Ok(())
+}
+
+fn create(
+    cfg: &mut LauncherSettings,
+    name: String,
+    version: &str,
+    icon: Option<String>,
+) -> Result<(), String> {
+    cfg.validate_instance_name(&name)?;
+    let icon = icon.unwrap_or_default();
+    if !icon.is_empty() && !PathBuf::from(&icon).is_file() {
+        return Err("The icon file not exists".to_string());
+    }
+    let versions = manifest_versions().map_err(|e| e.to_string())?;
+    let Some(version) = versions.into_iter().find(|v| v.get_version_id() == version) else {
+        return Err(format!("The version '{version}' not exists"));
+    };
+
+    let path = cfg.add_instance(
+        LauncherInstance {
+            name,
+            version: Some(version),
+            ..Default::default()
+        },
+        icon,
+    );
+    println!("{}", path.to_str().unwrap());
+    Ok(())
+}
+
+fn remove(cfg: &mut LauncherSettings, name: String) -> Result<(), String> {
+    find_instance(cfg, &name)?;
+    cfg.remove_instance(name);
+    Ok(())
+}
+
+fn verify(cfg: &LauncherSettings, name: &str) -> Result<(), String> {
+    let instance = find_instance(cfg, name)?;
+    let Some(version) = instance.version.as_ref() else {
+        return Err(format!("Instance '{name}' has no version selected"));
+    };
+    let report = verify_version(&version.get_version_id()).map_err(|e| e.to_string())?;
+    for (file, problem) in report.bad.iter() {
+        println!("{problem:?}: {}", file.path.display());
+    }
+    println!(
+        "{} files checked, {} missing and {} corrupt",
+        report.checked,
+        report.missing(),
+        report.corrupt()
+    );
+    if report.bad.is_empty() {
+        return Ok(());
+    }
+
+    let files = report
+        .bad
+        .into_iter()
+        .map(|(f, _)| f)
+        .collect::<Vec<FileCheck>>();
+    let (progress, progress_rcv) = DownloadProgress::new();
+    let worker = std::thread::spawn(move || repair_files(&files, progress));
+    print_progress(
+        &progress_rcv,
+        &format!("Repairing files of instance: {name}"),
+    );
+    worker
+        .join()
+        .map_err(|_| format!("The repair of '{name}' panicked"))?
+        .map_err(|e| e.to_string())?;
+    println!("Files of instance '{name}' repaired");
+    Ok(())
+}
+
+fn java(cmd: JavaCommands) -> Result<(), String> {
+    match cmd {
+        JavaCommands::List => {
+            for runtime in discover_runtimes() {
+                println!(
+                    "{:<4} {:<20} {}",
+                    runtime.major, runtime.version, runtime.path
+                );
+            }
+        }
+        JavaCommands::Install { archive } => {
+            let runtime = install_runtime(&archive).map_err(|e| e.to_string())?;
+            println!("Java {} installed at {}", runtime.version, runtime.path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use std::{
+    io::{BufRead, Write},
+    sync::mpsc::Receiver,
+};
+
+use anyhow::{anyhow, Result};
+use log::error;
+
+use crate::{
+    download_svc::{
+        download_version, format_bytes, manifest_versions, DownloadProgress,
+        DownloadProgressMessage,
+    },
+    java::find_runtime,
+    launch::spawn_instance,
+    settings::{LauncherInstance, LauncherSettings, MinecraftVersion},
+};
+
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+static HELP: &str = "Commands:
+  list                          List the instances
+  versions [type]               List the versions (release, snapshot, old_beta, old_alpha)
+  create <version> <name>       Create an instance using a version of the manifest
+  download <name>               Download the resources of the instance
+  launch <name>                 Launch the instance and wait until the game is closed
+  help                          Show this message
+  quit                          Exit from the launcher";
+
+/// Line oriented mode used when the launcher runs with `--no-gui`
+pub fn run_terminal() -> i32 {
+    let mut cfg = match LauncherSettings::new() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+    let mut versions = Vec::new();
+
+    println!("{HELP}");
+    let stdin = std::io::stdin();
+    loop {
+        print!("> ");
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                error!("Cannot read from stdin: {e}");
+                return 1;
+            }
+        }
+        let mut words = line.split_whitespace();
+        let Some(cmd) = words.next() else {
+            continue;
+        };
+        let args = words.collect::<Vec<&str>>();
+
+        let res = match cmd {
+            "list" | "ls" => {
+                list_instances(&cfg);
+                Ok(())
+            }
+            "versions" => list_versions(&mut versions, args.first().copied()),
+            "create" => create_instance(&mut cfg, &mut versions, &args),
+            "download" => download_instance(&mut cfg, &args.join(" ")),
+            "launch" => launch_instance(&mut cfg, &args.join(" ")),
+            "help" => {
+                println!("{HELP}");
+                Ok(())
+            }
+            "quit" | "exit" => break,
+            _ => Err(anyhow!(
+                "Unknown command '{cmd}', type 'help' to see the commands"
+            )),
+        };
+
+        if let Err(e) = res {
+            eprintln!("{e}");
+        }
+    }
+    0
+}
+
+fn list_instances(cfg: &LauncherSettings) {
+    if cfg.instances.is_empty() {
+        println!("There are no instances");
+        return;
+    }
+    for instance in cfg.instances.iter() {
+        let version = instance
+            .version
+            .as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "No Version".to_string());
+        let status = if instance.downloaded {
+            "Downloaded"
+        } else {
+            "Not Downloaded"
+        };
+        println!("{:<30} {:<30} {status}", instance.name, version);
+    }
+}
+
+fn load_versions(versions: &mut Vec<MinecraftVersion>) -> Result<()> {
+    if versions.is_empty() {
+        *versions = manifest_versions()?;
+    }
+    Ok(())
+}
+
+fn list_versions(versions: &mut Vec<MinecraftVersion>, kind: Option<&str>) -> Result<()> {
+    load_versions(versions)?;
+    let kind = kind.unwrap_or("release");
+    versions
+        .iter()
+        .filter(|v| v.get_version_type() == kind)
+        .for_each(|v| println!("{}", v.get_version_id()));
+    Ok(())
+}
+
+fn create_instance(
+    cfg: &mut LauncherSettings,
+    versions: &mut Vec<MinecraftVersion>,
+    args: &[&str],
+) -> Result<()> {
+    let [version, name @ ..] = args else {
+        return Err(anyhow!("Usage: create <version> <name>"));
+    };
+    let name = name.join(" ");
+    if name.is_empty() {
+        return Err(anyhow!("Usage: create <version> <name>"));
+    }
+    cfg.validate_instance_name(&name).map_err(|e| anyhow!(e))?;
+    load_versions(versions)?;
+    let Some(version) = versions.iter().find(|v| v.get_version_id() == *version) else {
+        return Err(anyhow!("The version '{version}' not exists"));
+    };
+
+    let path = cfg.add_instance(
+        LauncherInstance {
+            name: name.clone(),
+            version: Some(version.clone()),
+            ..Default::default()
+        },
+        String::new(),
+    );
+    println!("Instance '{name}' created at {path:?}");
+    Ok(())
+}
+
+fn download_instance(cfg: &mut LauncherSettings, name: &str) -> Result<()> {
+    let Some(instance) = cfg.instances.iter_mut().find(|i| i.name == name) else {
+        return Err(anyhow!("Instance '{name}' not exists"));
+    };
+    let Some(version) = instance.version.clone() else {
+        return Err(anyhow!("Instance '{name}' has no version selected"));
+    };
+
+    let version_id = version.get_version_id();
+    let (progress, progress_rcv) = DownloadProgress::new();
+    let worker = std::thread::spawn(move || download_version(&version, progress));
+    // the receiver is closed when the worker drops the reporter
+    print_progress(
+        &progress_rcv,
+        &format!("Downloading resources for instance: {name}"),
+    );
+    worker
+        .join()
+        .map_err(|_| anyhow!("The download of '{name}' panicked"))??;
+
+    instance.downloaded = true;
+    println!("Resources for instance are Downloaded: {name}");
+    if instance.java_path.is_empty() {
+        let java = find_runtime(&version_id);
+        instance.java_path = java.map(|r| r.path).unwrap_or_default();
+    }
+    instance.save_manifest()?;
+    Ok(())
+}
+
+/// Draw the progress until the worker sends `End`, `Failed` or drops the reporter
+pub(super) fn print_progress(progress_rcv: &Receiver<DownloadProgressMessage>, title: &str) {
+    let (mut curr, mut max, mut speed) = (0, 0, 0);
+    while let Ok(msg) = progress_rcv.recv() {
+        match msg {
+            DownloadProgressMessage::Setup(total) => {
+                println!("{title}");
+                (curr, max) = (0, total);
+            }
+            DownloadProgressMessage::Update(c, m) => (curr, max) = (c, m),
+            DownloadProgressMessage::Throughput(bytes) => speed = bytes,
+            DownloadProgressMessage::BatchStarted(_) | DownloadProgressMessage::FileFinished(_) => {
+                continue
+            }
+            DownloadProgressMessage::FileFailed { file, reason } => {
+                println!("\r{file}: {reason}");
+            }
+            DownloadProgressMessage::Retry { file, attempt } => {
+                println!("\rRetry {attempt} of {file}");
+            }
+            DownloadProgressMessage::Failed(reason) => {
+                println!("\nDownload failed: {reason}");
+                break;
+            }
+            DownloadProgressMessage::End => {
+                println!();
+                break;
+            }
+        }
+        draw_progress_bar(curr, max, speed);
+    }
+}
+
+fn draw_progress_bar(curr: u64, max: u64, speed: u64) {
+    let ratio = if max == 0 {
+        0.
+    } else {
+        (curr as f64 / max as f64).min(1.)
+    };
+    let filled = (ratio * PROGRESS_BAR_WIDTH as f64) as usize;
+    print!(
+        "\r[{}{}] {:>3}% ({curr}/{max}) {}/s   ",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_BAR_WIDTH - filled),
+        (ratio * 100.) as u8,
+        format_bytes(speed),
+    );
+    std::io::stdout().flush().unwrap();
+}
+
+fn launch_instance(cfg: &mut LauncherSettings, name: &str) -> Result<()> {
+    let Some(instance) = cfg.instances.iter().find(|i| i.name == name).cloned() else {
+        return Err(anyhow!("Instance '{name}' not exists"));
+    };
+    super::validate_session(cfg, &instance).map_err(|e| anyhow!(e))?;
+    println!("Launching Instance '{name}'");
+    let status = spawn_instance(&instance, cfg, false)?.wait()?;
+    println!("Instance '{name}' finished with: {status}");
+    Ok(())
+}
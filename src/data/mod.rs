@@ -13,6 +13,9 @@ pub static APP_INFO: app_dirs::AppInfo = app_dirs::AppInfo {
     author: "SergioRibera",
 };
 
+/// Used as icon of the instances created without one
+pub static DEFAULT_INSTANCE_ICON: &[u8] = include_bytes!("../../assets/app.png");
+
 pub static OPENMC_SECURE_KEY: Lazy<String> = Lazy::new(|| lc!(env!("OPENMC_ENCRYPT_KEY")));
 
 // on linux: ~/.config/{app_info.name}/{path}
@@ -34,3 +37,8 @@ pub fn data_path(path: &str) -> PathBuf {
     }
     res
 }
+
+/// Shared store of the versions, libraries and assets, used by all the instances
+pub fn store_path() -> PathBuf {
+    data_path("")
+}
@@ -0,0 +1,633 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use log::{debug, error, info};
+use mc_downloader::prelude::{DownloadData, DownloaderService, Reporter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{config_path, data_path},
+    settings::{LauncherInstance, MinecraftVersion},
+};
+
+use super::{
+    create_downloads_icons, create_faces_downloads, download_checked, fetch_version,
+    verify_version, DownloadProgressMessage, FileCheck,
+};
+
+/// Unfinished jobs, they continue on the next start of the launcher
+pub static JOBS_FILE: &str = "downloads.json";
+/// Files downloaded between each check of pause and cancel, small to apply them soon
+const BATCH_SIZE: usize = 20;
+const MAX_RUNNING_JOBS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobKind {
+    /// Download all the files of the version
+    Download,
+    /// Check the hashes and download again only the broken files
+    Verify,
+    /// Icons and faces used by the launcher
+    Assets,
+}
+
+impl ToString for JobKind {
+    fn to_string(&self) -> String {
+        match self {
+            JobKind::Download => "Download".to_string(),
+            JobKind::Verify => "Verify".to_string(),
+            JobKind::Assets => "Assets".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Cancelled,
+    Failed(String),
+    Finished,
+}
+
+impl JobStatus {
+    /// The job is not finished yet
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Queued | JobStatus::Running | JobStatus::Paused
+        )
+    }
+}
+
+impl ToString for JobStatus {
+    fn to_string(&self) -> String {
+        match self {
+            JobStatus::Queued => "Queued".to_string(),
+            JobStatus::Running => "Running".to_string(),
+            JobStatus::Paused => "Paused".to_string(),
+            JobStatus::Cancelled => "Cancelled".to_string(),
+            JobStatus::Failed(e) => format!("Failed: {e}"),
+            JobStatus::Finished => "Finished".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifySummary {
+    pub checked: usize,
+    pub missing: usize,
+    pub corrupt: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadJob {
+    pub id: u64,
+    /// `None` for the jobs of the launcher, ex: assets
+    pub instance: Option<String>,
+    pub version: Option<MinecraftVersion>,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    #[serde(skip)]
+    pub files_done: usize,
+    #[serde(skip)]
+    pub files_total: usize,
+    #[serde(skip)]
+    pub bytes: u64,
+    /// Sum of the totals reported by the downloader, it grows with each batch
+    #[serde(skip)]
+    pub bytes_total: u64,
+    /// Bytes per second of the last second
+    #[serde(skip)]
+    pub speed: u64,
+    /// Time downloading, without the time paused
+    #[serde(skip)]
+    pub elapsed: Duration,
+    /// What the job is downloading now, ex: a file or a batch of files
+    #[serde(skip)]
+    pub current: Option<String>,
+    /// Files that failed with the reason, a file is removed when a retry works
+    #[serde(skip)]
+    pub failures: Vec<(String, String)>,
+    #[serde(skip)]
+    pub retries: u32,
+    /// Files of the batch that is downloading
+    #[serde(skip)]
+    batch_files: usize,
+    /// Bytes and max reported by the downloader for the batch
+    #[serde(skip)]
+    batch_bytes: u64,
+    #[serde(skip)]
+    batch_max: u64,
+    #[serde(skip)]
+    pub summary: Option<VerifySummary>,
+    /// A worker is alive for the job, paused jobs keep their worker
+    #[serde(skip)]
+    started: bool,
+}
+
+impl DownloadJob {
+    fn new(
+        id: u64,
+        instance: Option<String>,
+        version: Option<MinecraftVersion>,
+        kind: JobKind,
+    ) -> Self {
+        Self {
+            id,
+            instance,
+            version,
+            kind,
+            status: JobStatus::Queued,
+            files_done: 0,
+            files_total: 0,
+            bytes: 0,
+            bytes_total: 0,
+            speed: 0,
+            elapsed: Duration::ZERO,
+            current: None,
+            failures: Vec::new(),
+            retries: 0,
+            batch_files: 0,
+            batch_bytes: 0,
+            batch_max: 0,
+            summary: None,
+            started: false,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self.instance.as_ref() {
+            Some(instance) => instance.clone(),
+            None => "Launcher Assets".to_string(),
+        }
+    }
+
+    /// Files downloaded counting the part of the batch downloading
+    fn files_progress(&self) -> f32 {
+        let batch = if self.batch_max == 0 {
+            0.
+        } else {
+            (self.batch_bytes as f32 / self.batch_max as f32).min(1.)
+        };
+        self.files_done as f32 + self.batch_files as f32 * batch
+    }
+
+    /// Value from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.files_total == 0 {
+            return 0.;
+        }
+        (self.files_progress() / self.files_total as f32).min(1.)
+    }
+
+    /// Time left estimated with the progress of the files
+    pub fn eta(&self) -> Option<Duration> {
+        let progress = self.progress();
+        if self.status != JobStatus::Running || progress <= 0. || progress >= 1. {
+            return None;
+        }
+        Some(self.elapsed.mul_f32((1. - progress) / progress))
+    }
+}
+
+/// Sum of the active jobs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueueTotals {
+    pub jobs: usize,
+    pub files_done: f32,
+    pub files_total: usize,
+    pub bytes: u64,
+    pub bytes_total: u64,
+    pub speed: u64,
+}
+
+impl QueueTotals {
+    /// Value from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.files_total == 0 {
+            return 0.;
+        }
+        (self.files_done / self.files_total as f32).min(1.)
+    }
+}
+
+type SharedJob = Arc<Mutex<DownloadJob>>;
+
+/// Queue of every download of the launcher, the jobs run on workers and the UI only reads
+/// snapshots of them
+#[derive(Default)]
+pub struct DownloadManager {
+    jobs: Vec<SharedJob>,
+    next_id: u64,
+    /// Finished jobs already returned by `poll`
+    reported: Vec<u64>,
+}
+
+impl DownloadManager {
+    /// Restore the jobs interrupted when the launcher was closed
+    pub fn load() -> Self {
+        let path = data_path("").join(JOBS_FILE);
+        let jobs = match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice::<Vec<DownloadJob>>(&content)
+                .map_err(|e| error!("Cannot read the download jobs {path:?}: {e}"))
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+        let next_id = jobs.iter().map(|j| j.id + 1).max().unwrap_or_default();
+        let jobs = jobs
+            .into_iter()
+            .filter(|j| j.status.is_active())
+            .map(|mut j| {
+                if j.status == JobStatus::Running {
+                    j.status = JobStatus::Queued;
+                }
+                info!("Download of '{}' restored: {:?}", j.name(), j.status);
+                Arc::new(Mutex::new(j))
+            })
+            .collect();
+        Self {
+            jobs,
+            next_id,
+            reported: Vec::new(),
+        }
+    }
+
+    pub fn save(&self) {
+        let jobs = self
+            .jobs()
+            .into_iter()
+            .filter(|j| j.status.is_active())
+            .collect::<Vec<DownloadJob>>();
+        let path = data_path("").join(JOBS_FILE);
+        // replace the file only when the write finish, same as the settings
+        let tmp_path = path.with_extension("json.tmp");
+        let res = serde_json::to_vec_pretty(&jobs)
+            .map_err(|e| e.to_string())
+            .and_then(|content| std::fs::write(&tmp_path, content).map_err(|e| e.to_string()))
+            .and_then(|_| std::fs::rename(&tmp_path, &path).map_err(|e| e.to_string()));
+        if let Err(e) = res {
+            error!("Cannot save the download jobs {path:?}: {e}");
+        }
+    }
+
+    fn push(&mut self, job: DownloadJob) {
+        self.jobs.push(Arc::new(Mutex::new(job)));
+        self.next_id += 1;
+        self.save();
+    }
+
+    pub fn enqueue(&mut self, instance: &LauncherInstance, kind: JobKind) -> Result<(), String> {
+        let Some(version) = instance.version.clone() else {
+            return Err(format!(
+                "Instance '{}' has no version selected",
+                instance.name
+            ));
+        };
+        if self.is_active(&instance.name) {
+            return Err(format!(
+                "Instance '{}' is already downloading",
+                instance.name
+            ));
+        }
+        self.push(DownloadJob::new(
+            self.next_id,
+            Some(instance.name.clone()),
+            Some(version),
+            kind,
+        ));
+        Ok(())
+    }
+
+    /// Queue the download of the icons and faces of the launcher
+    pub fn enqueue_assets(&mut self) -> Result<(), String> {
+        let active = self
+            .jobs()
+            .iter()
+            .any(|j| j.kind == JobKind::Assets && j.status.is_active());
+        if active {
+            return Err("The launcher assets are already downloading".to_string());
+        }
+        self.push(DownloadJob::new(self.next_id, None, None, JobKind::Assets));
+        Ok(())
+    }
+
+    fn find(&self, id: u64) -> Option<&SharedJob> {
+        self.jobs.iter().find(|j| j.lock().unwrap().id == id)
+    }
+
+    fn set_status(&self, id: u64, from: &[JobStatus], to: JobStatus) {
+        if let Some(job) = self.find(id) {
+            let mut job = job.lock().unwrap();
+            if from.contains(&job.status) {
+                debug!("Download of '{}': {:?} -> {to:?}", job.name(), job.status);
+                job.status = to;
+            }
+        }
+        self.save();
+    }
+
+    pub fn pause(&self, id: u64) {
+        self.set_status(
+            id,
+            &[JobStatus::Queued, JobStatus::Running],
+            JobStatus::Paused,
+        );
+    }
+
+    /// The worker of a paused job is still alive, it continues where it stopped
+    pub fn resume(&self, id: u64) {
+        let Some(job) = self.find(id) else {
+            return;
+        };
+        let started = job.lock().unwrap().started;
+        let to = if started {
+            JobStatus::Running
+        } else {
+            JobStatus::Queued
+        };
+        self.set_status(id, &[JobStatus::Paused], to);
+    }
+
+    pub fn cancel(&self, id: u64) {
+        self.set_status(
+            id,
+            &[JobStatus::Queued, JobStatus::Running, JobStatus::Paused],
+            JobStatus::Cancelled,
+        );
+    }
+
+    /// Snapshot of every job, the oldest first
+    pub fn jobs(&self) -> Vec<DownloadJob> {
+        self.jobs
+            .iter()
+            .map(|j| j.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// The unfinished job of the instance
+    pub fn job_of(&self, instance: &str) -> Option<DownloadJob> {
+        self.jobs()
+            .into_iter()
+            .find(|j| j.instance.as_deref() == Some(instance) && j.status.is_active())
+    }
+
+    pub fn is_active(&self, instance: &str) -> bool {
+        self.job_of(instance).is_some()
+    }
+
+    pub fn any_active(&self) -> bool {
+        self.jobs().iter().any(|j| j.status.is_active())
+    }
+
+    pub fn totals(&self) -> QueueTotals {
+        self.jobs().iter().filter(|j| j.status.is_active()).fold(
+            QueueTotals::default(),
+            |mut totals, j| {
+                totals.jobs += 1;
+                totals.files_done += j.files_progress();
+                totals.files_total += j.files_total;
+                totals.bytes += j.bytes;
+                totals.bytes_total += j.bytes_total;
+                totals.speed += j.speed;
+                totals
+            },
+        )
+    }
+
+    /// Remove the finished, failed and cancelled jobs
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|j| j.lock().unwrap().status.is_active());
+    }
+
+    /// Start the queued jobs and returns the jobs finished since the last call
+    pub fn poll(&mut self) -> Vec<DownloadJob> {
+        let mut running = self
+            .jobs()
+            .iter()
+            .filter(|j| j.started && j.status.is_active())
+            .count();
+        for job in self.jobs.iter() {
+            if running >= MAX_RUNNING_JOBS {
+                break;
+            }
+            let queued = {
+                let mut job = job.lock().unwrap();
+                let queued = job.status == JobStatus::Queued;
+                if queued {
+                    job.status = JobStatus::Running;
+                    job.started = true;
+                }
+                queued
+            };
+            if queued {
+                running += 1;
+                spawn_worker(job.clone());
+            }
+        }
+
+        let finished = self
+            .jobs()
+            .into_iter()
+            .filter(|j| !j.status.is_active() && !self.reported.contains(&j.id))
+            .collect::<Vec<DownloadJob>>();
+        if !finished.is_empty() {
+            self.reported.extend(finished.iter().map(|j| j.id));
+            self.save();
+        }
+        finished
+    }
+}
+
+fn spawn_worker(job: SharedJob) {
+    std::thread::spawn(move || {
+        let res = run_job(&job);
+        let mut job = job.lock().unwrap();
+        job.speed = 0;
+        match res {
+            // a cancelled job keeps its status
+            _ if job.status == JobStatus::Cancelled => {
+                info!("Download of '{}' cancelled", job.name())
+            }
+            Ok(_) => {
+                info!("Download of '{}' finished", job.name());
+                job.status = JobStatus::Finished;
+            }
+            Err(e) => {
+                error!("Download of '{}' failed: {e}", job.name());
+                job.status = JobStatus::Failed(e.to_string());
+            }
+        }
+    });
+}
+
+/// Block the worker meanwhile the job is paused, fails when the job is cancelled
+fn wait_while_paused(job: &SharedJob) -> Result<()> {
+    loop {
+        match job.lock().unwrap().status {
+            JobStatus::Paused => {}
+            JobStatus::Cancelled => return Err(anyhow!("Cancelled")),
+            _ => return Ok(()),
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+fn run_job(job: &SharedJob) -> Result<()> {
+    let (version, kind) = {
+        let job = job.lock().unwrap();
+        (job.version.clone(), job.kind)
+    };
+    let reporter = Arc::new(Mutex::new(JobReporter::new(job.clone())));
+    if kind == JobKind::Assets {
+        let downloads = [create_downloads_icons(), create_faces_downloads()].concat();
+        job.lock().unwrap().files_total = downloads.len();
+        return download_batches(job, &reporter, config_path(""), downloads);
+    }
+    let version_id = version
+        .ok_or_else(|| anyhow!("The job has no version"))?
+        .get_version_id();
+
+    wait_while_paused(job)?;
+    // the same pipeline of the terminal, the files left broken are repaired by batches
+    let report = match kind {
+        JobKind::Verify => verify_version(&version_id)?,
+        _ => fetch_version(&version_id, reporter.clone())?,
+    };
+    let files = report
+        .bad
+        .iter()
+        .map(|(f, _)| f.clone())
+        .collect::<Vec<FileCheck>>();
+    {
+        let mut job = job.lock().unwrap();
+        job.files_total = files.len();
+        job.summary = Some(VerifySummary {
+            checked: report.checked,
+            missing: report.missing(),
+            corrupt: report.corrupt(),
+        });
+    }
+    for batch in files.chunks(BATCH_SIZE) {
+        wait_while_paused(job)?;
+        job.lock().unwrap().batch_files = batch.len();
+        download_checked(batch, reporter.clone(), |msg| apply_event(job, msg))?;
+        let mut job = job.lock().unwrap();
+        job.files_done += batch.len();
+        job.batch_files = 0;
+    }
+    Ok(())
+}
+
+/// Keep on the job the file downloading and the failures
+fn apply_event(job: &SharedJob, msg: DownloadProgressMessage) {
+    let mut job = job.lock().unwrap();
+    match msg {
+        DownloadProgressMessage::BatchStarted(files) => {
+            job.current = Some(format!("{files} files of {}", job.files_total))
+        }
+        DownloadProgressMessage::FileFinished(file) => job.failures.retain(|(f, _)| *f != file),
+        DownloadProgressMessage::FileFailed { file, reason } => {
+            job.failures.retain(|(f, _)| *f != file);
+            job.failures.push((file, reason));
+        }
+        DownloadProgressMessage::Retry { .. } => job.retries += 1,
+        _ => {}
+    }
+}
+
+/// Download the files by batches, pause and cancel are checked between batches
+fn download_batches(
+    job: &SharedJob,
+    reporter: &Arc<Mutex<JobReporter>>,
+    root: PathBuf,
+    downloads: Vec<DownloadData>,
+) -> Result<()> {
+    let mut downloads = downloads.into_iter();
+    loop {
+        let batch = downloads
+            .by_ref()
+            .take(BATCH_SIZE)
+            .collect::<Vec<DownloadData>>();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        wait_while_paused(job)?;
+        let len = batch.len();
+        {
+            let mut job = job.lock().unwrap();
+            job.current = Some(format!(
+                "Files {} to {} of {}",
+                job.files_done + 1,
+                job.files_done + len,
+                job.files_total
+            ));
+            job.batch_files = len;
+        }
+        let service = DownloaderService::new(root.to_str().unwrap())
+            .with_downloads(batch)
+            .with_parallel_requests(50)
+            .to_owned();
+        service.run(Some(reporter.clone()))?;
+        let mut job = job.lock().unwrap();
+        job.files_done += len;
+        job.batch_files = 0;
+    }
+}
+
+/// Count the bytes and speed of the job
+struct JobReporter {
+    job: SharedJob,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl JobReporter {
+    fn new(job: SharedJob) -> Self {
+        Self {
+            job,
+            window_start: Instant::now(),
+            window_bytes: 0,
+        }
+    }
+}
+
+impl Reporter for JobReporter {
+    fn setup(&mut self, max_progress: u64) {
+        let mut job = self.job.lock().unwrap();
+        job.bytes_total += max_progress;
+        job.batch_bytes = 0;
+        job.batch_max = max_progress;
+        // the time paused between batches is not counted
+        self.window_start = Instant::now();
+        self.window_bytes = 0;
+    }
+
+    fn progress(&mut self, current: u64) {
+        self.window_bytes += current;
+        let window = self.window_start.elapsed();
+        let mut job = self.job.lock().unwrap();
+        job.bytes += current;
+        job.batch_bytes += current;
+        // pause and cancel are applied by the worker between batches, the reporter never blocks
+        if job.status == JobStatus::Paused {
+            job.speed = 0;
+        } else if window >= Duration::from_secs(1) {
+            job.speed = (self.window_bytes as f64 / window.as_secs_f64()) as u64;
+            job.elapsed += window;
+        }
+        if window >= Duration::from_secs(1) {
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+    }
+
+    fn done(&mut self) {
+        self.job.lock().unwrap().current = None;
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use log::{debug, error, info, warn};
+use mc_downloader::prelude::ClientDownloader;
+use serde::{Deserialize, Serialize};
+
+use crate::{data::data_path, settings::MinecraftVersion};
+
+/// Copy of the launcher manifest, used when there is no network
+pub static MANIFEST_CACHE: &str = "version_manifest.json";
+/// Time between refreshes meanwhile the launcher is offline
+const OFFLINE_RETRY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestCache {
+    /// Unix time of the last fetch
+    pub fetched: u64,
+    pub versions: Vec<MinecraftVersion>,
+}
+
+impl ManifestCache {
+    pub fn load() -> Option<Self> {
+        let path = data_path("").join(MANIFEST_CACHE);
+        let content = std::fs::read(&path).ok()?;
+        serde_json::from_slice(&content)
+            .map_err(|e| error!("Cannot read the manifest cache {path:?}: {e}"))
+            .ok()
+    }
+
+    pub fn save(&self) {
+        let path = data_path("").join(MANIFEST_CACHE);
+        let res = serde_json::to_vec(self)
+            .map_err(|e| e.to_string())
+            .and_then(|content| std::fs::write(&path, content).map_err(|e| e.to_string()));
+        if let Err(e) = res {
+            error!("Cannot save the manifest cache {path:?}: {e}");
+        }
+    }
+}
+
+/// Fetch the versions available on the launcher manifest and update the cache
+pub fn fetch_manifest() -> Result<ManifestCache> {
+    let versions = ClientDownloader::new()?
+        .get_list_versions()
+        .iter()
+        .map(MinecraftVersion::from)
+        .collect();
+    let cache = ManifestCache {
+        fetched: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        versions,
+    };
+    cache.save();
+    debug!("Manifest fetched: {} versions", cache.versions.len());
+    Ok(cache)
+}
+
+/// Versions of the launcher, starts from the cache and refresh it on background
+#[derive(Default)]
+pub struct VersionManifest {
+    pub versions: Vec<MinecraftVersion>,
+    /// Unix time of the versions, `None` when there is no cache
+    pub fetched: Option<u64>,
+    /// The last refresh failed
+    pub offline: bool,
+    refresh_rcv: Option<Receiver<Result<ManifestCache, String>>>,
+    last_refresh: Option<Instant>,
+}
+
+impl VersionManifest {
+    pub fn load() -> Self {
+        let mut manifest = match ManifestCache::load() {
+            Some(cache) => Self {
+                versions: cache.versions,
+                fetched: Some(cache.fetched),
+                ..Default::default()
+            },
+            None => Self::default(),
+        };
+        manifest.refresh();
+        manifest
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.refresh_rcv.is_some()
+    }
+
+    pub fn refresh(&mut self) {
+        if self.is_refreshing() {
+            return;
+        }
+        let (sender, rcv) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(fetch_manifest().map_err(|e| e.to_string()));
+        });
+        self.refresh_rcv = Some(rcv);
+        self.last_refresh = Some(Instant::now());
+    }
+
+    /// Returns true when the versions changed
+    pub fn poll(&mut self) -> bool {
+        if self.offline
+            && !self.is_refreshing()
+            && self.last_refresh.map(|t| t.elapsed()) >= Some(OFFLINE_RETRY)
+        {
+            self.refresh();
+        }
+        let Some(rcv) = self.refresh_rcv.as_ref() else {
+            return false;
+        };
+        // a worker that panicked never sends, it is handled as a failed refresh
+        let res = match rcv.try_recv() {
+            Ok(res) => res,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => Err("The refresh stopped".to_string()),
+        };
+        self.refresh_rcv = None;
+        match res {
+            Ok(cache) => {
+                if self.offline {
+                    info!("The manifest is reachable again");
+                }
+                self.offline = false;
+                self.fetched = Some(cache.fetched);
+                let changed = self.versions != cache.versions;
+                self.versions = cache.versions;
+                changed
+            }
+            Err(e) => {
+                warn!("Cannot refresh the manifest, using the cache: {e}");
+                self.offline = true;
+                false
+            }
+        }
+    }
+}
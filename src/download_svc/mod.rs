@@ -1,14 +1,30 @@
 mod faces;
 mod icons;
+mod jobs;
+mod manifest;
+mod verify;
 
-use std::sync::mpsc::{Receiver, SyncSender};
+use std::{
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
+use anyhow::Result;
 pub use faces::*;
 pub use icons::*;
-use log::info;
-use mc_downloader::prelude::{DownloaderService, Reporter};
+pub use jobs::*;
+use log::{debug, info, trace, warn};
+pub use manifest::*;
+use mc_downloader::prelude::{ClientDownloader, DownloadVersion, DownloaderService, Reporter};
+pub use verify::*;
 
-use crate::data::config_path;
+use crate::{
+    data::{config_path, store_path},
+    settings::MinecraftVersion,
+};
 
 pub fn download_extra_resources() -> DownloaderService {
     let cfg_path = config_path("");
@@ -25,61 +41,189 @@ pub fn download_extra_resources() -> DownloaderService {
         .to_owned()
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Download the client, libraries and assets of the version with the downloader of the store,
+/// which also extracts the natives. Returns the check of the files after it
+pub fn fetch_version<R: Reporter + Send + 'static>(
+    version_id: &str,
+    reporter: Arc<Mutex<R>>,
+) -> Result<VerifyReport> {
+    debug!("Start download of version: {version_id}");
+    ClientDownloader::new()?.download_version(
+        version_id,
+        store_path().to_str().unwrap(),
+        Some(Arc::new(Mutex::new(NoEndReporter(reporter)))),
+    )?;
+    debug!("Downloaded version: {version_id}");
+    verify_version(version_id)
+}
+
+/// Download the version and repair the files left broken, blocks until finish
+pub fn download_version(version: &MinecraftVersion, progress: DownloadProgress) -> Result<()> {
+    let report = match fetch_version(
+        &version.get_version_id(),
+        Arc::new(Mutex::new(progress.clone())),
+    ) {
+        Ok(report) => report,
+        Err(e) => {
+            progress.failed(&e.to_string());
+            return Err(e);
+        }
+    };
+    let files = report
+        .bad
+        .into_iter()
+        .map(|(f, _)| f)
+        .collect::<Vec<FileCheck>>();
+    repair_files(&files, progress)
+}
+
+/// Fetch the versions available on the launcher manifest, the cache is used without network
+pub fn manifest_versions() -> Result<Vec<MinecraftVersion>> {
+    match fetch_manifest() {
+        Ok(cache) => Ok(cache.versions),
+        Err(e) => match ManifestCache::load() {
+            Some(cache) => {
+                warn!("Cannot fetch the manifest, using the cache: {e}");
+                Ok(cache.versions)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// `1536` is `1.5 KiB`
+pub fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit < units.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", units[unit])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DownloadProgressMessage {
     Setup(u64),
+    /// Current and max bytes
     Update(u64, u64),
+    /// Bytes per second of the last second
+    Throughput(u64),
+    /// A run of the downloader started with this number of files, the downloader
+    /// not reports each file
+    BatchStarted(usize),
+    FileFinished(String),
+    FileFailed {
+        file: String,
+        reason: String,
+    },
+    /// The file is downloaded again, `attempt` starts at 1
+    Retry {
+        file: String,
+        attempt: u32,
+    },
+    /// The download stopped, the worker sends nothing more
+    Failed(String),
     End,
 }
 
+/// Reporter that sends the progress through a channel, the messages are never lost and the
+/// downloader never waits for the receiver
 #[derive(Clone)]
 pub struct DownloadProgress {
     curr_progress: u64,
     max_progress: u64,
-    sender: SyncSender<DownloadProgressMessage>,
+    window_start: Instant,
+    window_bytes: u64,
+    sender: Sender<DownloadProgressMessage>,
 }
 
 impl DownloadProgress {
     pub fn new() -> (Self, Receiver<DownloadProgressMessage>) {
-        let (sender, recv) = std::sync::mpsc::sync_channel::<DownloadProgressMessage>(1);
+        let (sender, recv) = std::sync::mpsc::channel::<DownloadProgressMessage>();
         (
             Self {
                 curr_progress: 0,
                 max_progress: 0,
+                window_start: Instant::now(),
+                window_bytes: 0,
                 sender,
             },
             recv,
         )
     }
+
+    /// The receiver can be closed, ex: the window was closed, the download continues
+    pub fn send(&self, msg: DownloadProgressMessage) {
+        if let Err(e) = self.sender.send(msg) {
+            debug!("Progress receiver closed, message lost: {:?}", e.0);
+        }
+    }
+
+    pub fn batch_started(&self, files: usize) {
+        self.send(DownloadProgressMessage::BatchStarted(files));
+    }
+
+    pub fn file_finished(&self, file: &str) {
+        self.send(DownloadProgressMessage::FileFinished(file.to_string()));
+    }
+
+    pub fn file_failed(&self, file: &str, reason: &str) {
+        self.send(DownloadProgressMessage::FileFailed {
+            file: file.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    pub fn retry(&self, file: &str, attempt: u32) {
+        self.send(DownloadProgressMessage::Retry {
+            file: file.to_string(),
+            attempt,
+        });
+    }
+
+    pub fn failed(&self, reason: &str) {
+        self.send(DownloadProgressMessage::Failed(reason.to_string()));
+    }
 }
 
 impl Reporter for DownloadProgress {
     fn setup(&mut self, max_progress: u64) {
         info!("Setup Reporter: {max_progress}");
         self.max_progress = max_progress;
-        self.sender
-            .send(DownloadProgressMessage::Setup(max_progress))
-            .unwrap();
+        self.send(DownloadProgressMessage::Setup(max_progress));
     }
 
     fn progress(&mut self, current: u64) {
         self.curr_progress += current;
-        info!(
+        self.window_bytes += current;
+        trace!(
             "Setup progress\nIncoming: {current}\nCurrent: {}\nMax: {}",
-            self.curr_progress, self.max_progress
+            self.curr_progress,
+            self.max_progress
         );
         if current > 0 {
-            self.sender
-                .send(DownloadProgressMessage::Update(
-                    self.curr_progress,
-                    self.max_progress,
-                ))
-                .unwrap();
+            self.send(DownloadProgressMessage::Update(
+                self.curr_progress,
+                self.max_progress,
+            ));
+        }
+        let window = self.window_start.elapsed();
+        if window >= Duration::from_secs(1) {
+            let speed = (self.window_bytes as f64 / window.as_secs_f64()) as u64;
+            self.send(DownloadProgressMessage::Throughput(speed));
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
         }
     }
 
     fn done(&mut self) {
         info!("Done progress");
-        self.sender.send(DownloadProgressMessage::End).unwrap();
+        self.send(DownloadProgressMessage::End);
     }
 }
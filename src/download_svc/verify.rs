@@ -0,0 +1,336 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use mc_downloader::prelude::{DownloadData, DownloaderService, Reporter};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+
+use crate::data::store_path;
+
+use super::{DownloadProgress, DownloadProgressMessage};
+
+/// Times a file is downloaded again when the hash not match
+pub const MAX_RETRIES: u32 = 3;
+static ASSETS_URL: &str = "https://resources.download.minecraft.net";
+
+#[cfg(target_os = "linux")]
+static OS_NAME: &str = "linux";
+#[cfg(target_os = "macos")]
+static OS_NAME: &str = "osx";
+#[cfg(windows)]
+static OS_NAME: &str = "windows";
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+static OS_NAME: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileProblem {
+    Missing,
+    Corrupt,
+}
+
+/// File of the shared store with the hash expected by the version json
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCheck {
+    pub path: PathBuf,
+    pub url: String,
+    pub sha1: String,
+}
+
+impl FileCheck {
+    /// Name used on the progress messages
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn check(&self) -> Result<(), FileProblem> {
+        match sha1_file(&self.path) {
+            Ok(hash) if hash.eq_ignore_ascii_case(&self.sha1) => Ok(()),
+            Ok(_) => Err(FileProblem::Corrupt),
+            Err(_) => Err(FileProblem::Missing),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub bad: Vec<(FileCheck, FileProblem)>,
+}
+
+impl VerifyReport {
+    pub fn missing(&self) -> usize {
+        self.bad
+            .iter()
+            .filter(|(_, p)| *p == FileProblem::Missing)
+            .count()
+    }
+
+    pub fn corrupt(&self) -> usize {
+        self.bad.len() - self.missing()
+    }
+}
+
+pub fn sha1_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    let content = std::fs::read(path).map_err(|e| anyhow!("Cannot read {path:?}: {e}"))?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+fn file_check(json: &Value, path: PathBuf) -> Option<FileCheck> {
+    Some(FileCheck {
+        path,
+        url: json.get("url")?.as_str()?.to_string(),
+        sha1: json.get("sha1")?.as_str()?.to_string(),
+    })
+}
+
+/// Evaluate the `rules` of a library, ex: natives only for one os
+fn library_allowed(library: &Value) -> bool {
+    let Some(rules) = library.get("rules").and_then(|r| r.as_array()) else {
+        return true;
+    };
+    let mut allowed = false;
+    for rule in rules {
+        let os = rule.pointer("/os/name").and_then(|n| n.as_str());
+        if os.is_none() || os == Some(OS_NAME) {
+            allowed = rule.get("action").and_then(|a| a.as_str()) == Some("allow");
+        }
+    }
+    allowed
+}
+
+/// Classifier with the natives of this os, the other classifiers are other os,
+/// sources or javadoc
+fn native_classifier(library: &Value) -> Option<String> {
+    let arch = if cfg!(target_pointer_width = "64") {
+        "64"
+    } else {
+        "32"
+    };
+    library
+        .get("natives")
+        .and_then(|n| n.get(OS_NAME))
+        .and_then(|n| n.as_str())
+        .map(|n| n.replace("${arch}", arch))
+}
+
+/// The client jar, libraries, asset index and asset objects of the version
+fn version_files(version_id: &str) -> Result<Vec<FileCheck>> {
+    let store = store_path();
+    let version_dir = store.join("versions").join(version_id);
+    let json = read_json(&version_dir.join(format!("{version_id}.json")))?;
+    let mut files = Vec::new();
+
+    if let Some(client) = json.pointer("/downloads/client") {
+        files.extend(file_check(
+            client,
+            version_dir.join(format!("{version_id}.jar")),
+        ));
+    }
+
+    let libraries_dir = store.join("libraries");
+    let libraries = json.get("libraries").and_then(|l| l.as_array());
+    for library in libraries
+        .into_iter()
+        .flatten()
+        .filter(|l| library_allowed(l))
+    {
+        let Some(downloads) = library.get("downloads") else {
+            continue;
+        };
+        let natives = native_classifier(library).and_then(|classifier| {
+            downloads
+                .get("classifiers")
+                .and_then(|c| c.get(classifier.as_str()))
+        });
+        let artifacts = downloads.get("artifact").into_iter().chain(natives);
+        for artifact in artifacts {
+            if let Some(path) = artifact.get("path").and_then(|p| p.as_str()) {
+                files.extend(file_check(artifact, libraries_dir.join(path)));
+            }
+        }
+    }
+
+    let assets_dir = store.join("assets");
+    if let Some(index) = json.get("assetIndex") {
+        let index_id = index
+            .get("id")
+            .and_then(|i| i.as_str())
+            .ok_or_else(|| anyhow!("The asset index of '{version_id}' has no id"))?;
+        let index_path = assets_dir.join("indexes").join(format!("{index_id}.json"));
+        files.extend(file_check(index, index_path.clone()));
+
+        match read_json(&index_path) {
+            Ok(index) => {
+                let objects = index.get("objects").and_then(|o| o.as_object());
+                for object in objects.into_iter().flat_map(|o| o.values()) {
+                    let Some(hash) = object.get("hash").and_then(|h| h.as_str()) else {
+                        continue;
+                    };
+                    let prefix = &hash[..2.min(hash.len())];
+                    files.push(FileCheck {
+                        path: assets_dir.join("objects").join(prefix).join(hash),
+                        url: format!("{ASSETS_URL}/{prefix}/{hash}"),
+                        sha1: hash.to_string(),
+                    });
+                }
+            }
+            // the index is reported as missing, its objects are checked on the next verify
+            Err(e) => warn!("Cannot read the asset index of '{version_id}': {e}"),
+        }
+    }
+    Ok(files)
+}
+
+/// Check the hashes of every file of the version on the shared store
+pub fn verify_version(version_id: &str) -> Result<VerifyReport> {
+    let files = version_files(version_id)?;
+    let mut report = VerifyReport {
+        checked: files.len(),
+        ..Default::default()
+    };
+    for file in files {
+        let Err(problem) = file.check() else {
+            continue;
+        };
+        debug!("{problem:?} file: {:?}", file.path);
+        report.bad.push((file, problem));
+    }
+    info!(
+        "Verified '{version_id}': {} files, {} missing, {} corrupt",
+        report.checked,
+        report.missing(),
+        report.corrupt()
+    );
+    Ok(report)
+}
+
+/// Forward the progress to the reporter but not the end, the files are checked after each
+/// run of the downloader
+pub(super) struct NoEndReporter<R: Reporter>(pub(super) Arc<Mutex<R>>);
+
+impl<R: Reporter> Reporter for NoEndReporter<R> {
+    fn setup(&mut self, max_progress: u64) {
+        self.0.lock().unwrap().setup(max_progress);
+    }
+
+    fn progress(&mut self, current: u64) {
+        self.0.lock().unwrap().progress(current);
+    }
+
+    fn done(&mut self) {}
+}
+
+/// Download the files and check the hash of each one, the broken files are downloaded again
+/// up to `MAX_RETRIES` times. The events of each file are sent to `on_event`
+pub fn download_checked<R: Reporter + Send + 'static>(
+    files: &[FileCheck],
+    reporter: Arc<Mutex<R>>,
+    mut on_event: impl FnMut(DownloadProgressMessage),
+) -> Result<()> {
+    let mut pending = files.to_vec();
+    let mut attempt = 0;
+    loop {
+        let downloads = pending
+            .iter()
+            .map(|f| {
+                if let Some(parent) = f.path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                // corrupt files are replaced
+                if f.path.is_file() {
+                    std::fs::remove_file(&f.path)?;
+                }
+                Ok(DownloadData::new(&f.url, f.path.to_str().unwrap()))
+            })
+            .collect::<Result<Vec<DownloadData>>>()?;
+        on_event(DownloadProgressMessage::BatchStarted(downloads.len()));
+
+        let service = DownloaderService::new(store_path().to_str().unwrap())
+            .with_downloads(downloads)
+            .with_parallel_requests(50)
+            .to_owned();
+        // the files not downloaded are reported by the check
+        let run_error = service
+            .run(Some(Arc::new(Mutex::new(NoEndReporter(reporter.clone())))))
+            .err()
+            .map(|e| e.to_string());
+
+        let mut failed = Vec::new();
+        for file in pending {
+            match file.check() {
+                Ok(_) => on_event(DownloadProgressMessage::FileFinished(file.name())),
+                Err(problem) => {
+                    let reason = match (problem, run_error.as_ref()) {
+                        (FileProblem::Corrupt, _) => "The hash not match".to_string(),
+                        (FileProblem::Missing, Some(e)) => format!("Not downloaded: {e}"),
+                        (FileProblem::Missing, None) => "Not downloaded".to_string(),
+                    };
+                    warn!("Cannot download {:?}: {reason}", file.path);
+                    on_event(DownloadProgressMessage::FileFailed {
+                        file: file.name(),
+                        reason,
+                    });
+                    failed.push(file);
+                }
+            }
+        }
+        if failed.is_empty() {
+            return Ok(());
+        }
+        attempt += 1;
+        if attempt > MAX_RETRIES {
+            return Err(anyhow!(
+                "{} files failed after {MAX_RETRIES} retries, ex: {}",
+                failed.len(),
+                failed[0].name()
+            ));
+        }
+        for file in failed.iter() {
+            on_event(DownloadProgressMessage::Retry {
+                file: file.name(),
+                attempt,
+            });
+        }
+        pending = failed;
+    }
+}
+
+/// Download again only the files with problems
+pub fn repair_files(files: &[FileCheck], progress: DownloadProgress) -> Result<()> {
+    info!("Files to repair: {}", files.len());
+    let events = progress.clone();
+    let reporter = Arc::new(Mutex::new(progress));
+    match download_checked(files, reporter.clone(), |msg| events.send(msg)) {
+        Ok(_) => {
+            reporter.lock().unwrap().done();
+            Ok(())
+        }
+        Err(e) => {
+            events.failed(&e.to_string());
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use zip::ZipArchive;
+
+use crate::data::data_path;
+
+use super::{cache_runtime, JavaRuntime, JAVA_BIN};
+
+/// Extract a `.zip`, `.tar.gz` or `.tar.xz` JDK/JRE archive into `data_path("java")`
+pub fn install_runtime(archive: &Path) -> Result<JavaRuntime> {
+    if !archive.is_file() {
+        return Err(anyhow!("The archive {archive:?} not exists"));
+    }
+    let name = archive
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| {
+            n.trim_end_matches(".zip")
+                .trim_end_matches(".tar.gz")
+                .trim_end_matches(".tgz")
+                .trim_end_matches(".tar.xz")
+        })
+        .ok_or_else(|| anyhow!("Invalid archive name {archive:?}"))?;
+    let mut dest = data_path("java");
+    dest.push(name);
+    if dest.exists() {
+        return Err(anyhow!("The runtime '{name}' is already installed"));
+    }
+    std::fs::create_dir_all(&dest)?;
+
+    if let Err(e) = extract(archive, &dest) {
+        std::fs::remove_dir_all(&dest)?;
+        return Err(e);
+    }
+
+    let Some(runtime) = find_java_bin(&dest, 4).and_then(|p| JavaRuntime::probe(&p)) else {
+        std::fs::remove_dir_all(&dest)?;
+        return Err(anyhow!(
+            "The archive {archive:?} not contains a java runtime"
+        ));
+    };
+    info!("Java runtime installed: {runtime:?}");
+    cache_runtime(&runtime);
+    Ok(runtime)
+}
+
+/// The zip files are extracted by the launcher, the tar files by `tar`
+fn extract(archive: &Path, dest: &Path) -> Result<()> {
+    let is_zip = archive
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("zip"))
+        .unwrap_or(false);
+    if is_zip {
+        let mut zip = ZipArchive::new(File::open(archive)?)
+            .map_err(|e| anyhow!("Cannot read {archive:?}: {e}"))?;
+        // keeps the permissions of the files, ex: bin/java is executable
+        return zip
+            .extract(dest)
+            .map_err(|e| anyhow!("Cannot extract {archive:?}: {e}"));
+    }
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dest)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!("Cannot extract {archive:?}: {status}"));
+    }
+    Ok(())
+}
+
+fn find_java_bin(dir: &Path, depth: u8) -> Option<PathBuf> {
+    let bin = dir.join("bin").join(JAVA_BIN);
+    if bin.is_file() {
+        return Some(bin);
+    }
+    if depth == 0 {
+        return None;
+    }
+    dir.read_dir()
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .find_map(|e| find_java_bin(&e.path(), depth - 1))
+}
@@ -0,0 +1,294 @@
+mod install;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
+
+use log::{debug, trace};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::data::data_path;
+
+pub use install::*;
+
+#[cfg(windows)]
+pub static JAVA_BIN: &str = "java.exe";
+#[cfg(not(windows))]
+pub static JAVA_BIN: &str = "java";
+
+#[cfg(target_os = "linux")]
+static JVM_DIRS: &[&str] = &[
+    "/usr/lib/jvm",
+    "/usr/lib64/jvm",
+    "/usr/java",
+    "/opt/java",
+    "/opt",
+];
+#[cfg(target_os = "macos")]
+static JVM_DIRS: &[&str] = &[
+    "/Library/Java/JavaVirtualMachines",
+    "/opt/homebrew/opt",
+    "/usr/local/opt",
+];
+#[cfg(windows)]
+static JVM_DIRS: &[&str] = &[
+    "C:\\Program Files\\Java",
+    "C:\\Program Files\\Eclipse Adoptium",
+    "C:\\Program Files\\Microsoft",
+    "C:\\Program Files\\Zulu",
+];
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+static JVM_DIRS: &[&str] = &[];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JavaRuntime {
+    pub path: String,
+    pub version: String,
+    pub major: u8,
+}
+
+impl JavaRuntime {
+    /// Run `java -version` and read the version of the runtime
+    pub fn probe(path: &Path) -> Option<Self> {
+        let output = Command::new(path).arg("-version").output().ok()?;
+        // java prints the version on stderr
+        let out = String::from_utf8_lossy(&output.stderr);
+        let version = parse_java_version(&out)?;
+        trace!("Java found {path:?}: {version}");
+        Some(Self {
+            path: path.to_str()?.to_string(),
+            major: java_major(&version)?,
+            version,
+        })
+    }
+
+    /// Installed by the launcher into `data_path("java")`
+    pub fn is_managed(&self) -> bool {
+        Path::new(&self.path).starts_with(data_path("java"))
+    }
+}
+
+/// Get the version from the output of `java -version`, ex: `openjdk version "17.0.2" 2022-01-18`
+pub fn parse_java_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find(|l| l.contains("version"))
+        .and_then(|l| l.split('"').nth(1))
+        .map(|v| v.to_string())
+}
+
+/// `1.8.0_291` is Java 8, `17.0.2` is Java 17
+pub fn java_major(version: &str) -> Option<u8> {
+    let mut parts = version.split(['.', '_', '-', '+']);
+    let first = parts.next()?.parse::<u8>().ok()?;
+    if first == 1 {
+        parts.next()?.parse::<u8>().ok()
+    } else {
+        Some(first)
+    }
+}
+
+/// Java major version needed by a Minecraft version id
+pub fn required_java(mc_version: &str) -> u8 {
+    // snapshots: 24w14a
+    if let Some((year, week)) = mc_version.split_once('w') {
+        if let (Ok(year), Ok(week)) = (
+            year.parse::<u16>(),
+            week.trim_end_matches(char::is_alphabetic).parse::<u16>(),
+        ) {
+            return match (year, week) {
+                (y, w) if y > 24 || (y == 24 && w >= 14) => 21,
+                (y, w) if y > 21 || (y == 21 && w >= 44) => 17,
+                (21, w) if w >= 19 => 16,
+                _ => 8,
+            };
+        }
+    }
+    // releases and pre-releases: 1.20.5, 1.18-pre2, 1.20.5-rc1
+    let release = mc_version.split(['-', ' ']).next().unwrap_or_default();
+    let mut parts = release.split('.').map(|p| p.parse::<u16>().unwrap_or(0));
+    let (major, minor, patch) = (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    );
+    if major != 1 {
+        return 8;
+    }
+    match (minor, patch) {
+        (m, p) if m > 20 || (m == 20 && p >= 5) => 21,
+        (m, _) if m >= 18 => 17,
+        (17, _) => 16,
+        _ => 8,
+    }
+}
+
+fn java_in_home(home: &Path) -> Option<PathBuf> {
+    [
+        home.join("bin").join(JAVA_BIN),
+        home.join("Contents")
+            .join("Home")
+            .join("bin")
+            .join(JAVA_BIN),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+fn candidates() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Some(home) = std::env::var_os("JAVA_HOME") {
+        paths.extend(java_in_home(Path::new(&home)));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(
+            std::env::split_paths(&path)
+                .map(|p| p.join(JAVA_BIN))
+                .filter(|p| p.is_file()),
+        );
+    }
+    let managed = data_path("java");
+    let dirs = std::iter::once(managed).chain(JVM_DIRS.iter().map(PathBuf::from));
+    for dir in dirs {
+        let Ok(entries) = dir.read_dir() else {
+            continue;
+        };
+        paths.extend(entries.flatten().filter_map(|e| java_in_home(&e.path())));
+    }
+
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for p in paths {
+        let p = p.canonicalize().unwrap_or(p);
+        if !unique.contains(&p) {
+            unique.push(p);
+        }
+    }
+    unique
+}
+
+/// Runtimes found by the first scan, run `java -version` of each candidate is slow
+static RUNTIMES: Lazy<Mutex<Option<Vec<JavaRuntime>>>> = Lazy::new(Mutex::default);
+
+/// Scan `JAVA_HOME`, `PATH` and the common JVM directories, only the first call
+/// runs the scan and the next ones use the runtimes found
+pub fn discover_runtimes() -> Vec<JavaRuntime> {
+    if let Some(runtimes) = RUNTIMES.lock().unwrap().as_ref() {
+        return runtimes
+            .iter()
+            .filter(|r| Path::new(&r.path).is_file())
+            .cloned()
+            .collect();
+    }
+    // the lock is not held meanwhile each candidate runs, the ui reads the cache
+    let runtimes = candidates()
+        .iter()
+        .filter_map(|p| JavaRuntime::probe(p))
+        .collect::<Vec<JavaRuntime>>();
+    debug!("Java runtimes found: {runtimes:?}");
+    let mut cache = RUNTIMES.lock().unwrap();
+    // another scan could finish first
+    cache.get_or_insert(runtimes).clone()
+}
+
+/// Run the scan on background, the ui not waits for it on the first launch
+pub fn preload_runtimes() {
+    std::thread::spawn(|| {
+        discover_runtimes();
+    });
+}
+
+/// Add a runtime installed after the scan
+fn cache_runtime(runtime: &JavaRuntime) {
+    if let Some(runtimes) = RUNTIMES.lock().unwrap().as_mut() {
+        runtimes.push(runtime.clone());
+    }
+}
+
+/// Choose the runtime for a Minecraft version, only the exact major version is used because
+/// the old versions not start with a newer java, the runtimes installed by the launcher win
+pub fn find_runtime(mc_version: &str) -> Option<JavaRuntime> {
+    let required = required_java(mc_version);
+    let mut runtimes = discover_runtimes()
+        .into_iter()
+        .filter(|r| r.major == required)
+        .collect::<Vec<JavaRuntime>>();
+    runtimes.sort_by_key(|r| !r.is_managed());
+    runtimes.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_for_each_version() {
+        let cases = [
+            ("1.7.10", 8),
+            ("1.12.2", 8),
+            ("1.16.5", 8),
+            ("1.17", 16),
+            ("1.17.1", 16),
+            ("1.18-pre2", 17),
+            ("1.18.2", 17),
+            ("1.20.4", 17),
+            ("1.20.5-rc1", 21),
+            ("1.20.5", 21),
+            ("1.21", 21),
+            ("21w19a", 16),
+            ("21w44a", 17),
+            ("24w13a", 17),
+            ("24w14a", 21),
+            ("25w02a", 21),
+            ("b1.7.3", 8),
+            ("rd-132211", 8),
+        ];
+        for (version, java) in cases {
+            assert_eq!(required_java(version), java, "{version}");
+        }
+    }
+
+    #[test]
+    fn major_of_each_version() {
+        let cases = [
+            ("1.8.0_291", Some(8)),
+            ("1.8.0", Some(8)),
+            ("9", Some(9)),
+            ("11.0.12", Some(11)),
+            ("17.0.2", Some(17)),
+            ("17-ea", Some(17)),
+            ("21+35", Some(21)),
+            ("", None),
+            ("openjdk", None),
+        ];
+        for (version, major) in cases {
+            assert_eq!(java_major(version), major, "{version}");
+        }
+    }
+
+    #[test]
+    fn version_of_each_output() {
+        let cases = [
+            (
+                "openjdk version \"17.0.2\" 2022-01-18\nOpenJDK Runtime Environment",
+                Some("17.0.2"),
+            ),
+            (
+                "java version \"1.8.0_291\"\nJava(TM) SE Runtime Environment",
+                Some("1.8.0_291"),
+            ),
+            (
+                "Picked up _JAVA_OPTIONS: -Xmx1g\nopenjdk version \"21\" 2023-09-19",
+                Some("21"),
+            ),
+            ("command not found", None),
+            ("", None),
+        ];
+        for (output, version) in cases {
+            assert_eq!(parse_java_version(output).as_deref(), version, "{output}");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use log::debug;
+
+pub static CRASH_REPORTS_DIR: &str = "crash-reports";
+
+/// Report written by Minecraft on `<gameDir>/crash-reports/crash-<date>-client.txt`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrashReport {
+    pub path: PathBuf,
+    pub description: String,
+    pub stacktrace: String,
+    pub content: String,
+}
+
+impl CrashReport {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse(path, content))
+    }
+
+    /// The description is followed by a blank line and the stack trace of the error
+    pub fn parse(path: &Path, content: String) -> Self {
+        let mut lines = content.lines();
+        let description = lines
+            .by_ref()
+            .find_map(|l| l.strip_prefix("Description:"))
+            .map(|d| d.trim().to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+        let stacktrace = lines
+            .skip_while(|l| l.trim().is_empty())
+            .take_while(|l| !l.trim().is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        Self {
+            path: path.to_path_buf(),
+            description,
+            stacktrace,
+            content,
+        }
+    }
+
+    /// First line of the stack trace, ex: `java.lang.NullPointerException: ...`
+    pub fn error(&self) -> &str {
+        self.stacktrace.lines().next().unwrap_or_default()
+    }
+}
+
+/// Newest crash report of the game directory written after `since`
+pub fn find_crash_report(game_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    let dir = game_dir.join(CRASH_REPORTS_DIR);
+    let report = dir
+        .read_dir()
+        .ok()?
+        .flatten()
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+            let is_report = e.path().extension().map(|x| x == "txt").unwrap_or(false);
+            (is_report && modified >= since).then(|| (modified, e.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path);
+    debug!("Crash report found on {dir:?}: {report:?}");
+    report
+}
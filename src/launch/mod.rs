@@ -0,0 +1,169 @@
+mod crash;
+mod supervisor;
+
+use std::{
+    path::Path,
+    process::{Child, Command, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use mc_bootstrap::ClientBootstrap;
+
+use crate::{
+    data::store_path,
+    java::find_runtime,
+    settings::{LaunchOptions, LauncherInstance, LauncherSettings},
+};
+
+pub use crash::*;
+pub use supervisor::*;
+
+pub static DEFAULT_JAVA: &str = "java";
+
+/// Resolve the java binary of the instance, when the instance has none
+/// it looks for a compatible runtime and falls back to the one on `PATH`
+pub fn java_binary(instance: &LauncherInstance) -> String {
+    if !instance.java_path.is_empty() {
+        return instance.java_path.clone();
+    }
+    instance
+        .version
+        .as_ref()
+        .and_then(|v| find_runtime(&v.get_version_id()))
+        .map(|r| r.path)
+        .unwrap_or_else(|| DEFAULT_JAVA.to_string())
+}
+
+pub fn create_bootstrap(
+    instance: &LauncherInstance,
+    cfg: &LauncherSettings,
+    java: &str,
+) -> Result<ClientBootstrap> {
+    let Some(version) = instance.version.clone() else {
+        return Err(anyhow!(
+            "Instance '{}' has no version selected",
+            instance.name
+        ));
+    };
+    if !instance.downloaded {
+        return Err(anyhow!(
+            "Instance '{}' is not downloaded yet",
+            instance.name
+        ));
+    }
+    let session = cfg.session_for(instance).map_err(|e| anyhow!(e))?;
+    if !session.is_logged() {
+        return Err(anyhow!("There is no session to launch the instance"));
+    }
+    if session.is_expired() {
+        return Err(anyhow!(
+            "The session of {} expired, log in again",
+            session.name
+        ));
+    }
+
+    // the bootstrap resolves the versions, libraries and assets from the shared store,
+    // the game directory is replaced later by the folder of the instance
+    Ok(ClientBootstrap::new(
+        &session.access_token,
+        store_path().to_str().unwrap(),
+        java,
+        &session.name,
+        &session.uuid,
+        &version.get_version_id(),
+        &version.get_version_type(),
+    ))
+}
+
+/// Build the java process of the instance without start it
+pub fn create_command(instance: &LauncherInstance, cfg: &LauncherSettings) -> Result<Command> {
+    let java = java_binary(instance);
+    let bootstrap = create_bootstrap(instance, cfg, &java)?;
+    let options = instance.options.inherit(&cfg.launch_defaults);
+    let game_dir = Path::new(&instance.path);
+    if !game_dir.is_dir() {
+        return Err(anyhow!(
+            "The folder of instance '{}' not exists: {game_dir:?}",
+            instance.name
+        ));
+    }
+    let mut args = bootstrap.build_args()?;
+    set_game_dir(&mut args, game_dir);
+    apply_options(&mut args, &options);
+    debug!("Launch arguments for '{}': {args:?}", instance.name);
+
+    let mut cmd = Command::new(java);
+    cmd.args(args).current_dir(game_dir);
+    if let Some(env) = options.env.as_ref() {
+        cmd.envs(env.iter().filter(|(k, _)| !k.is_empty()).cloned());
+    }
+    Ok(cmd)
+}
+
+/// Each instance keeps its saves, mods, resourcepacks, etc. on its own folder
+fn set_game_dir(args: &mut Vec<String>, game_dir: &Path) {
+    let game_dir = game_dir.to_string_lossy().to_string();
+    match args.iter().position(|a| a == "--gameDir") {
+        Some(i) if i + 1 < args.len() => args[i + 1] = game_dir,
+        _ => args.extend(["--gameDir".to_string(), game_dir]),
+    }
+}
+
+/// JVM options go before the main class and the game arguments at the end
+fn apply_options(args: &mut Vec<String>, options: &LaunchOptions) {
+    let mut jvm_args = Vec::new();
+    // 0 means the default of the JVM
+    if let Some(min_memory) = options.min_memory.filter(|m| *m > 0) {
+        jvm_args.push(format!("-Xms{min_memory}M"));
+    }
+    if let Some(max_memory) = options.max_memory.filter(|m| *m > 0) {
+        jvm_args.push(format!("-Xmx{max_memory}M"));
+    }
+    if let Some(flags) = options.jvm_args.as_ref() {
+        jvm_args.extend(flags.split_whitespace().map(|a| a.to_string()));
+    }
+    args.splice(0..0, jvm_args);
+
+    if let Some((width, height)) = options.resolution.filter(|(w, h)| *w > 0 && *h > 0) {
+        args.extend([
+            "--width".to_string(),
+            width.to_string(),
+            "--height".to_string(),
+            height.to_string(),
+        ]);
+    }
+    if options.fullscreen == Some(true) {
+        args.push("--fullscreen".to_string());
+    }
+    if let Some(game_args) = options.game_args.as_ref() {
+        args.extend(game_args.split_whitespace().map(|a| a.to_string()));
+    }
+}
+
+/// Start the instance, when `piped` is true the stdout/stderr of the game are captured
+pub fn spawn_instance(
+    instance: &LauncherInstance,
+    cfg: &LauncherSettings,
+    piped: bool,
+) -> Result<Child> {
+    if instance.missing {
+        return Err(anyhow!(
+            "The folder of instance '{}' not exists",
+            instance.name
+        ));
+    }
+    let mut cmd = create_command(instance, cfg)?;
+    if piped {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    } else {
+        cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    }
+    let child = cmd.spawn()?;
+    info!(
+        "Instance '{}' launched with pid {}",
+        instance.name,
+        child.id()
+    );
+    Ok(child)
+}
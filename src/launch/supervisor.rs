@@ -0,0 +1,270 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{BufRead, BufReader, Read},
+    process::Child,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use log::{debug, error, info, warn};
+
+use crate::settings::{LaunchRecord, LauncherInstance, LauncherSettings};
+
+use super::spawn_instance;
+
+/// Max lines of log kept for each game process
+pub const LOG_CAPACITY: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Running,
+    Exited(i32),
+    /// Finished with an error code or killed by a signal
+    Crashed(Option<i32>),
+}
+
+impl GameState {
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            GameState::Running => None,
+            GameState::Exited(code) => Some(*code),
+            GameState::Crashed(code) => *code,
+        }
+    }
+}
+
+impl Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameState::Running => write!(f, "Running"),
+            GameState::Exited(code) => write!(f, "Exited ({code})"),
+            GameState::Crashed(Some(code)) => write!(f, "Crashed ({code})"),
+            GameState::Crashed(None) => write!(f, "Crashed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Other,
+}
+
+impl LogLevel {
+    /// Minecraft prints lines like `[12:00:00] [Render thread/WARN]: message`
+    pub fn parse(line: &str) -> Self {
+        let head = line.split("]:").next().unwrap_or_default();
+        if head.contains("/ERROR]") || head.contains("/FATAL]") {
+            LogLevel::Error
+        } else if head.contains("/WARN]") {
+            LogLevel::Warn
+        } else if head.contains("/INFO]") {
+            LogLevel::Info
+        } else if head.contains("/DEBUG]") || head.contains("/TRACE]") {
+            LogLevel::Debug
+        } else if line.starts_with("Exception") || line.starts_with("\tat ") {
+            LogLevel::Error
+        } else {
+            LogLevel::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub text: String,
+    pub stderr: bool,
+}
+
+type LogBuffer = Arc<Mutex<VecDeque<LogLine>>>;
+
+/// A game started by the launcher, the output is captured until the process finish
+pub struct GameProcess {
+    pub instance: String,
+    pub started: SystemTime,
+    child: Mutex<Child>,
+    state: Mutex<GameState>,
+    finished: Mutex<Option<SystemTime>>,
+    killed: AtomicBool,
+    logs: LogBuffer,
+}
+
+impl GameProcess {
+    pub fn spawn(instance: &LauncherInstance, cfg: &LauncherSettings) -> Result<Self> {
+        let mut child = spawn_instance(instance, cfg, true)?;
+        let logs = LogBuffer::default();
+        if let Some(stdout) = child.stdout.take() {
+            capture(&instance.name, stdout, logs.clone(), false);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            capture(&instance.name, stderr, logs.clone(), true);
+        }
+        Ok(Self {
+            instance: instance.name.clone(),
+            started: SystemTime::now(),
+            child: Mutex::new(child),
+            state: Mutex::new(GameState::Running),
+            finished: Mutex::new(None),
+            killed: AtomicBool::new(false),
+            logs,
+        })
+    }
+
+    /// Check if the process has finished without block
+    pub fn state(&self) -> GameState {
+        let mut state = self.state.lock().unwrap();
+        if *state != GameState::Running {
+            return *state;
+        }
+        match self.child.lock().unwrap().try_wait() {
+            Ok(Some(status)) => {
+                *state = match status.code() {
+                    Some(0) => GameState::Exited(0),
+                    // stopped by the user, it is not a crash
+                    code if self.killed.load(Ordering::Relaxed) => {
+                        GameState::Exited(code.unwrap_or(-1))
+                    }
+                    code => GameState::Crashed(code),
+                };
+                *self.finished.lock().unwrap() = Some(SystemTime::now());
+                info!("Instance '{}' finished: {status}", self.instance);
+            }
+            Ok(None) => {}
+            Err(e) => error!("Cannot get the status of '{}': {e}", self.instance),
+        }
+        *state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state() == GameState::Running
+    }
+
+    pub fn kill(&self) -> Result<()> {
+        if !self.is_running() {
+            return Ok(());
+        }
+        self.killed.store(true, Ordering::Relaxed);
+        self.child.lock().unwrap().kill()?;
+        warn!("Instance '{}' killed by the user", self.instance);
+        Ok(())
+    }
+
+    /// Entry for the launch history, `None` meanwhile the game is running
+    pub fn record(&self) -> Option<LaunchRecord> {
+        let state = self.state();
+        let finished = (*self.finished.lock().unwrap())?;
+        let started = self
+            .started
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let duration = finished
+            .duration_since(self.started)
+            .unwrap_or_default()
+            .as_secs();
+        Some(LaunchRecord {
+            started,
+            duration,
+            exit_code: state.code(),
+        })
+    }
+
+    /// Read the captured lines without copy them, the oldest first.
+    /// The capture of new lines waits until `f` returns
+    pub fn with_logs<T>(&self, f: impl FnOnce(&VecDeque<LogLine>) -> T) -> T {
+        f(&self.logs.lock().unwrap())
+    }
+}
+
+fn capture<R: Read + Send + 'static>(name: &str, reader: R, logs: LogBuffer, stderr: bool) {
+    let name = name.to_string();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&buf).trim_end().to_string();
+                    let mut logs = logs.lock().unwrap();
+                    if logs.len() == LOG_CAPACITY {
+                        logs.pop_front();
+                    }
+                    logs.push_back(LogLine {
+                        level: LogLevel::parse(&text),
+                        text,
+                        stderr,
+                    });
+                }
+                Err(e) => {
+                    error!("Cannot read the output of '{name}': {e}");
+                    break;
+                }
+            }
+        }
+        debug!("Output of '{name}' closed");
+    });
+}
+
+/// Owns the games launched from the GUI, one process per instance
+#[derive(Default)]
+pub struct Supervisor {
+    processes: Vec<GameProcess>,
+    /// Finished processes already reported by `poll`
+    reported: Vec<String>,
+}
+
+impl Supervisor {
+    pub fn launch(&mut self, instance: &LauncherInstance, cfg: &LauncherSettings) -> Result<()> {
+        if self.is_running(&instance.name) {
+            return Err(anyhow!("Instance '{}' is already running", instance.name));
+        }
+        let process = GameProcess::spawn(instance, cfg)?;
+        self.processes.retain(|p| p.instance != instance.name);
+        self.reported.retain(|n| n != &instance.name);
+        self.processes.push(process);
+        Ok(())
+    }
+
+    pub fn get(&self, instance: &str) -> Option<&GameProcess> {
+        self.processes.iter().find(|p| p.instance == instance)
+    }
+
+    pub fn is_running(&self, instance: &str) -> bool {
+        self.get(instance).map(|p| p.is_running()).unwrap_or(false)
+    }
+
+    pub fn any_running(&self) -> bool {
+        self.processes.iter().any(|p| p.is_running())
+    }
+
+    pub fn kill(&self, instance: &str) -> Result<()> {
+        match self.get(instance) {
+            Some(process) => process.kill(),
+            None => Err(anyhow!("Instance '{instance}' is not running")),
+        }
+    }
+
+    /// Returns the processes that finished since the last call
+    pub fn poll(&mut self) -> Vec<(String, GameState)> {
+        let mut finished = Vec::new();
+        for process in self.processes.iter() {
+            let state = process.state();
+            if state != GameState::Running && !self.reported.contains(&process.instance) {
+                self.reported.push(process.instance.clone());
+                finished.push((process.instance.clone(), state));
+            }
+        }
+        finished
+    }
+}
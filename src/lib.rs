@@ -1,15 +1,23 @@
 pub mod args;
+pub mod auth;
+pub mod cli;
 pub mod data;
 pub mod download_svc;
+pub mod java;
+pub mod launch;
 pub mod resources;
 pub mod screens;
+pub mod secrets;
 pub mod settings;
 pub mod widgets;
 
 #[cfg(feature = "inspect")]
 use egui_inspect::EguiInspect;
 
+use auth::SessionValidator;
+use download_svc::{DownloadManager, VersionManifest};
 use egui_toast::Toasts;
+use launch::Supervisor;
 use widgets::{create_toast, Modal, ModalBuilder};
 
 #[macro_use]
@@ -23,11 +31,24 @@ pub static MODAL_ID: &str = "__openmc__modal";
 pub struct MainState {
     pub sub_title: String,
     pub create_instance: bool,
+    pub edit_instance: bool,
     pub changed_face: bool,
     #[cfg_attr(feature = "inspect", inspect(hide))]
     pub toasts: Toasts,
     #[cfg_attr(feature = "inspect", inspect(hide))]
     pub modal: Modal,
+    /// Games launched from the GUI
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub supervisor: Supervisor,
+    /// Downloads and verifications of instances
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub downloads: DownloadManager,
+    /// Versions of the manifest, from the cache when there is no network
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub manifest: VersionManifest,
+    /// Sessions validated before launch the instances
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub sessions: SessionValidator,
 }
 
 impl Default for MainState {
@@ -35,12 +56,17 @@ impl Default for MainState {
         Self {
             sub_title: Default::default(),
             create_instance: false,
+            edit_instance: false,
             changed_face: false,
             toasts: create_toast(),
             modal: ModalBuilder::default()
                 .set_id(MODAL_ID)
                 .set_size_percent((0.6, 0.6))
                 .build(),
+            supervisor: Supervisor::default(),
+            downloads: DownloadManager::default(),
+            manifest: VersionManifest::default(),
+            sessions: SessionValidator::default(),
         }
     }
 }
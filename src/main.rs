@@ -1,28 +1,44 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::path::Path;
+
+use clap::Parser;
 use data::APP_NAME;
 use egui_stylist::StylistState;
 use openmc_lite::{
+    args::OpenMCArgs,
+    auth::SessionStatus,
+    cli::{run_command, run_terminal},
     data,
-    download_svc::download_extra_resources,
+    download_svc::{DownloadJob, DownloadManager, JobKind, JobStatus, VersionManifest},
+    java::preload_runtimes,
+    launch::{find_crash_report, CrashReport, GameState},
     resources,
-    screens::{self, Account, AccountType, Instances},
+    screens::{self, utils::start_instance, Account, AccountType, Instances},
     settings, widgets, MainState,
 };
 use resources::ResourceLoader;
-use screens::{tab_buttons, CreateInstance, ViewType};
-use settings::LauncherSettings;
-use widgets::{open_file_dialog, TitleBar};
+use screens::{tab_buttons, CrashViewer, CreateInstance, EditInstance, UnlockSecrets, ViewType};
+use settings::{LauncherSettings, SettingsError};
+use widgets::{
+    add_toast, add_toast_with_action, open_file_dialog, take_toast_action, OpenMCToastKind,
+    TitleBar, ToastAction,
+};
 
 #[cfg(feature = "inspect")]
 use egui_inspect::EguiInspect;
 
-use mc_downloader::prelude::{ClientDownloader, DownloaderService};
-
 fn main() -> Result<(), eframe::Error> {
     env_logger::Builder::from_env(env_logger::Env::new().filter_or("OPENMC_LOG", "warn"))
         .format_timestamp(None)
         .init();
+    let opts = OpenMCArgs::parse();
+    if let Some(cmd) = opts.command {
+        std::process::exit(run_command(cmd));
+    }
+    if opts.no_gui {
+        std::process::exit(run_terminal());
+    }
     let icon_data = {
         let image = image::load_from_memory(include_bytes!("../assets/app.png"))
             .expect("failed to load icon");
@@ -60,41 +76,48 @@ pub struct MainApplication {
     account_view: Account,
     instances_widget: Instances,
     create_widget: CreateInstance,
-    downloader: Option<DownloaderService>,
+    edit_widget: EditInstance,
+    crash_viewer: CrashViewer,
+    settings_error: Option<SettingsError>,
+    unlock_secrets: UnlockSecrets,
     state: MainState,
 }
 
 impl MainApplication {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let launcher_config = LauncherSettings::new();
-        let mut theme = launcher_config.theme.apply(&cc.egui_ctx);
-        theme.set_file_dialog_function(Box::new(open_file_dialog));
-        log::debug!("Theme Loaded {:?}", launcher_config.theme);
-
-        let account_view = {
-            let account_type = if launcher_config.session.is_logged() {
-                Some(AccountType::from(launcher_config.session.account_origin()))
-            } else {
-                None
-            };
-            Account::new(account_type, &launcher_config)
+        let (launcher_config, settings_error) = match LauncherSettings::new() {
+            Ok(cfg) => (cfg, None),
+            Err(e) => {
+                log::error!("Cannot load settings: {e}");
+                (LauncherSettings::default().with_args(), Some(e))
+            }
         };
-        let mc = ClientDownloader::new().unwrap();
+        let theme = load_theme(&launcher_config, &cc.egui_ctx);
+        preload_runtimes();
+        let mut state = MainState {
+            downloads: DownloadManager::load(),
+            manifest: VersionManifest::load(),
+            ..Default::default()
+        };
+        if !launcher_config.exists_assets {
+            queue_assets(&mut state);
+        }
+        let create_widget = CreateInstance::new(&state.manifest.versions);
+        let edit_widget = EditInstance::new(&state.manifest.versions);
 
         Self {
             launcher_config: launcher_config.clone(),
             theme,
-            account_view,
-            state: MainState::default(),
+            settings_error,
+            unlock_secrets: UnlockSecrets::default(),
+            account_view: create_account_view(&launcher_config),
+            state,
             resources: ResourceLoader::default(),
             instances_widget: Instances::default(),
-            create_widget: CreateInstance::new(&mc),
+            create_widget,
+            edit_widget,
+            crash_viewer: CrashViewer::default(),
             titlebar: TitleBar::new(&launcher_config),
-            downloader: if !launcher_config.exists_assets {
-                Some(download_extra_resources())
-            } else {
-                None
-            },
             curr_view: if launcher_config.instances.is_empty() {
                 ViewType::Instances
             } else {
@@ -102,12 +125,243 @@ impl MainApplication {
             },
         }
     }
+
+    fn replace_config(&mut self, ctx: &eframe::egui::Context, cfg: LauncherSettings) {
+        self.theme = load_theme(&cfg, ctx);
+        self.account_view = create_account_view(&cfg);
+        self.titlebar = TitleBar::new(&cfg);
+        if !cfg.exists_assets {
+            queue_assets(&mut self.state);
+        }
+        self.launcher_config = cfg;
+    }
+
+    fn notify_crash(&mut self, name: &str, game_state: GameState) {
+        let started = self.state.supervisor.get(name).map(|p| p.started);
+        let report = self
+            .launcher_config
+            .instances
+            .iter()
+            .find(|i| i.name == name)
+            .zip(started)
+            .and_then(|(i, started)| find_crash_report(Path::new(&i.path), started))
+            .and_then(|path| CrashReport::load(&path).ok());
+        let title = format!("Instance '{name}' {game_state}");
+        match report {
+            Some(report) => add_toast_with_action(
+                &mut self.state.toasts,
+                &title,
+                &report.description,
+                OpenMCToastKind::Error,
+                ToastAction::OpenCrashReport(report.path.to_string_lossy().to_string()),
+            ),
+            None => add_toast(
+                &mut self.state.toasts,
+                &title,
+                "The game closed without crash report, check the console",
+                OpenMCToastKind::Error,
+            ),
+        }
+    }
+
+    /// Notify when a game finish and keep drawing while the console receives output
+    fn watch_games(&mut self, ctx: &eframe::egui::Context) {
+        for (name, game_state) in self.state.supervisor.poll() {
+            if let Some(record) = self.state.supervisor.get(&name).and_then(|p| p.record()) {
+                self.launcher_config.record_launch(&name, record);
+            }
+            if let GameState::Crashed(_) = game_state {
+                self.notify_crash(&name, game_state);
+                continue;
+            }
+            add_toast(
+                &mut self.state.toasts,
+                "Instance",
+                &format!("Instance '{name}' {game_state}"),
+                OpenMCToastKind::Info,
+            );
+        }
+        if self.state.supervisor.any_running() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+    }
+
+    fn finish_download(&mut self, job: &DownloadJob) {
+        let cfg = &mut self.launcher_config;
+        if job.kind == JobKind::Assets {
+            cfg.check_assets();
+            return;
+        }
+        let java_path = job
+            .version
+            .as_ref()
+            .and_then(|v| cfg.allocate_java(&v.get_version_id()));
+        let Some(instance) = cfg
+            .instances
+            .iter_mut()
+            .find(|i| Some(&i.name) == job.instance.as_ref())
+        else {
+            return;
+        };
+        instance.downloaded = true;
+        if instance.java_path.is_empty() {
+            instance.java_path = java_path.unwrap_or_default();
+        }
+        if let Err(e) = instance.save_manifest() {
+            log::error!("Cannot save manifest of '{}': {e}", instance.name);
+        }
+        let message = match (job.kind, job.summary.as_ref()) {
+            (JobKind::Verify, Some(summary)) => format!(
+                "{} files checked, {} missing and {} corrupt",
+                summary.checked, summary.missing, summary.corrupt
+            ),
+            _ => format!("Resources for instance are Downloaded: {}", job.name()),
+        };
+        add_toast(
+            &mut self.state.toasts,
+            "Instance",
+            &message,
+            OpenMCToastKind::Info,
+        );
+    }
+
+    /// Update the versions of the forms when the manifest is refreshed
+    fn watch_manifest(&mut self, ctx: &eframe::egui::Context) {
+        if self.state.manifest.poll() {
+            self.create_widget
+                .set_versions(&self.state.manifest.versions);
+            self.edit_widget.set_versions(&self.state.manifest.versions);
+        }
+        if self.state.manifest.is_refreshing() || self.state.manifest.offline {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+
+    /// Launch the instances whose session was validated, the expired sessions
+    /// that cannot be renewed open the login
+    fn watch_sessions(&mut self, ctx: &eframe::egui::Context) {
+        for (name, status) in self.state.sessions.poll() {
+            match status {
+                SessionStatus::Valid => {}
+                SessionStatus::Refreshed(session) => self.launcher_config.update_account(session),
+                SessionStatus::Relogin(reason) => {
+                    add_toast(
+                        &mut self.state.toasts,
+                        "Account",
+                        &format!("{reason}, log in again to launch '{name}'"),
+                        OpenMCToastKind::Warn,
+                    );
+                    self.account_view.relogin(&self.launcher_config);
+                    self.state.modal.open(ctx);
+                    continue;
+                }
+                SessionStatus::Failed(e) => {
+                    add_toast(
+                        &mut self.state.toasts,
+                        "Launch Failed",
+                        &format!("Cannot validate the session: {e}"),
+                        OpenMCToastKind::Error,
+                    );
+                    continue;
+                }
+            }
+            let Some(instance) = self
+                .launcher_config
+                .instances
+                .iter()
+                .find(|i| i.name == name)
+                .cloned()
+            else {
+                continue;
+            };
+            start_instance(&instance, &self.launcher_config, &mut self.state);
+        }
+        if self.state.sessions.any_validating() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+    }
+
+    /// Start the queued downloads and notify when a job finish
+    fn watch_downloads(&mut self, ctx: &eframe::egui::Context) {
+        for job in self.state.downloads.poll() {
+            match &job.status {
+                JobStatus::Finished => self.finish_download(&job),
+                JobStatus::Failed(e) => add_toast(
+                    &mut self.state.toasts,
+                    "Instance",
+                    &format!("Cannot download '{}': {e}", job.name()),
+                    OpenMCToastKind::Error,
+                ),
+                JobStatus::Cancelled => add_toast(
+                    &mut self.state.toasts,
+                    "Instance",
+                    &format!("Download of '{}' cancelled", job.name()),
+                    OpenMCToastKind::Info,
+                ),
+                _ => {}
+            }
+        }
+        if self.state.downloads.any_active() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+    }
+}
+
+fn queue_assets(state: &mut MainState) {
+    if state.downloads.enqueue_assets().is_ok() {
+        add_toast(
+            &mut state.toasts,
+            "Extra Assets",
+            "Downloading extra assets for launcher, ex: icons, sounds, etc",
+            OpenMCToastKind::Info,
+        );
+    }
+}
+
+fn load_theme(cfg: &LauncherSettings, ctx: &eframe::egui::Context) -> StylistState {
+    let mut theme = cfg.theme.apply(ctx);
+    theme.set_file_dialog_function(Box::new(open_file_dialog));
+    log::debug!("Theme Loaded {:?}", cfg.theme);
+    theme
+}
+
+fn create_account_view(cfg: &LauncherSettings) -> Account {
+    let account_type = if cfg.session.is_logged() {
+        Some(AccountType::from(cfg.session.account_origin()))
+    } else {
+        None
+    };
+    Account::new(account_type, cfg)
 }
 
 impl eframe::App for MainApplication {
     fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
         #[cfg(feature = "debug_ui")]
         ctx.set_debug_on_hover(true);
+        if let Some(e) = self.settings_error.as_ref() {
+            let res = widgets::CentralPanel::default()
+                .show(ctx, |ui| screens::settings_recovery(ui, frame, e))
+                .inner;
+            match res {
+                Some(Ok(cfg)) => {
+                    self.settings_error = None;
+                    self.replace_config(ctx, cfg);
+                }
+                Some(Err(e)) => self.settings_error = Some(e),
+                None => {}
+            }
+            return;
+        }
+        if self.unlock_secrets.is_pending(&self.launcher_config) {
+            widgets::CentralPanel::default().show(ctx, |ui| {
+                self.unlock_secrets.show(ui, &mut self.launcher_config)
+            });
+            return;
+        }
+        self.watch_games(ctx);
+        self.watch_downloads(ctx);
+        self.watch_manifest(ctx);
+        self.watch_sessions(ctx);
         widgets::CentralPanel::default().show(ctx, |ui| {
             #[cfg(feature = "inspect")]
             egui::Window::new("(Debug) Stats")
@@ -136,22 +390,33 @@ impl eframe::App for MainApplication {
                     ui.max_rect(),
                     &mut self.state,
                     &mut self.launcher_config,
-                    &mut self.downloader,
                 );
                 ui.add_space(10.);
-                if !self.state.create_instance {
+                if self.state.edit_instance {
+                    self.edit_widget.show(
+                        ui,
+                        &mut self.theme,
+                        &mut self.launcher_config,
+                        &mut self.state,
+                    );
+                } else if !self.state.create_instance {
                     tab_buttons(ui, &mut self.curr_view);
                     ui.add_space(10.);
                     match self.curr_view {
-                        ViewType::Home => {
-                            screens::home(ui, &mut self.launcher_config, &self.resources)
-                        }
+                        ViewType::Home => screens::home(
+                            ui,
+                            &mut self.launcher_config,
+                            &self.resources,
+                            &mut self.state,
+                        ),
                         ViewType::Instances => self.instances_widget.show(
                             ui,
                             &mut self.launcher_config,
                             &mut self.create_widget,
+                            &mut self.edit_widget,
                             &mut self.state,
                         ),
+                        ViewType::Downloads => screens::downloads(ui, &mut self.state),
                         ViewType::Preferences => {
                             screens::preferences(ui, &mut self.theme, &mut self.launcher_config)
                         }
@@ -175,12 +440,20 @@ impl eframe::App for MainApplication {
                     &mut self.launcher_config,
                 );
             });
+            if let Some(ToastAction::OpenCrashReport(path)) = take_toast_action(ctx) {
+                self.crash_viewer.open(ui, Path::new(&path));
+            }
+            self.crash_viewer.show(ui);
             // Toasts/Notification Area
             self.state.toasts.show(ctx);
         });
     }
 
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        // never overwrite the file that the user has not recovered yet
+        if self.settings_error.is_some() {
+            return;
+        }
         self.launcher_config.save();
     }
 }
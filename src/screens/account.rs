@@ -1,13 +1,22 @@
 #![allow(unused)]
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
+    time::Duration,
+};
 
-use egui::{Button, Layout, RichText, Ui};
+use egui::{Button, Color32, Layout, RichText, Ui};
 
 use crate::{
+    auth::{login_device_code, AuthEndpoints, DeviceCode, LoginStep},
     data::config_path,
     resources::{icon::Icon, ResourceLoader},
-    settings::LauncherSettings,
-    widgets::{GridWrapped, GridWrappedBuilder, ImageButton},
+    settings::{offline_uuid, AccountOrigin, LauncherSettings, UserSession},
+    widgets::{add_toast, GridWrapped, GridWrappedBuilder, ImageButton, OpenMCToastKind},
     MainState,
 };
 
@@ -24,12 +33,64 @@ enum AccountStep {
     View,
 }
 
-impl From<String> for AccountType {
-    fn from(v: String) -> Self {
-        match v.as_str() {
-            "MINECRAFT" => Self::Minecraft,
-            "MOJANG" | "MICROSOFT" => Self::Mojang,
-            _ => Self::Local,
+enum LoginMessage {
+    Code(DeviceCode),
+    Step(LoginStep),
+    Done(UserSession),
+    Failed(String),
+}
+
+/// Device code login running on background
+struct MicrosoftLogin {
+    rcv: Receiver<LoginMessage>,
+    cancel: Arc<AtomicBool>,
+    code: Option<DeviceCode>,
+    step: Option<LoginStep>,
+    error: Option<String>,
+}
+
+impl MicrosoftLogin {
+    fn start(endpoints: AuthEndpoints) -> Self {
+        let (sender, rcv) = std::sync::mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = cancel.clone();
+        std::thread::spawn(move || {
+            let send = |sender: &Sender<LoginMessage>, msg| {
+                let _ = sender.send(msg);
+            };
+            let res = login_device_code(
+                &endpoints,
+                worker_cancel,
+                |code| send(&sender, LoginMessage::Code(code.clone())),
+                |step| send(&sender, LoginMessage::Step(step)),
+            );
+            match res {
+                Ok(session) => send(&sender, LoginMessage::Done(session)),
+                Err(e) => send(&sender, LoginMessage::Failed(e.to_string())),
+            }
+        });
+        Self {
+            rcv,
+            cancel,
+            code: None,
+            step: None,
+            error: None,
+        }
+    }
+}
+
+impl Drop for MicrosoftLogin {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl From<AccountOrigin> for AccountType {
+    fn from(v: AccountOrigin) -> Self {
+        match v {
+            AccountOrigin::Minecraft => Self::Minecraft,
+            AccountOrigin::Mojang | AccountOrigin::Microsoft => Self::Mojang,
+            AccountOrigin::Local => Self::Local,
         }
     }
 }
@@ -41,6 +102,12 @@ pub struct Account {
     faces: Vec<(String, Icon)>,
     face_grid: GridWrapped<u8>,
     selected_icon: Option<Icon>,
+    login: Option<MicrosoftLogin>,
+    /// Id of the account shown, the active account can change from the title bar
+    session_id: String,
+    /// The name of the local account was edited, ask before change its uuid
+    name_edited: bool,
+    ask_uuid: bool,
 }
 
 impl Account {
@@ -74,25 +141,41 @@ impl Account {
             is_logged,
             faces: items,
             curr_step: AccountStep::View,
-            selected_icon: if cfg.session.face_img.is_empty() {
-                None
-            } else {
-                Some(
-                    Icon::image_from_path(
-                        cfg.session.face_img.as_str(),
-                        cfg.session.face_img.as_str(),
-                        egui_extras::image::FitTo::Size(50, 50),
-                    )
-                    .unwrap(),
-                )
-            },
+            selected_icon: face_icon(&cfg.session.face_img),
             face_grid: GridWrappedBuilder::default()
                 .show_search()
                 .set_enabled(true)
                 .set_cell_size((70., 70.))
                 .set_items((0u8..faces_len).collect::<Vec<u8>>())
                 .build(),
+            login: None,
+            session_id: cfg.session.id.clone(),
+            name_edited: false,
+            ask_uuid: false,
+        }
+    }
+
+    /// Start the Microsoft login again, used when a session cannot be renewed
+    pub fn relogin(&mut self, cfg: &LauncherSettings) {
+        self.login = Some(MicrosoftLogin::start(cfg.auth.clone()));
+        self.account_type = Some(AccountType::Mojang);
+        self.curr_step = AccountStep::Loading;
+    }
+
+    /// Show the active account when it was switched or removed
+    fn sync_session(&mut self, cfg: &LauncherSettings) {
+        if self.session_id == cfg.session.id || matches!(self.curr_step, AccountStep::Loading) {
+            return;
         }
+        self.session_id = cfg.session.id.clone();
+        self.account_type = if cfg.session.is_logged() {
+            Some(AccountType::from(cfg.session.account_origin()))
+        } else {
+            None
+        };
+        self.selected_icon = face_icon(&cfg.session.face_img);
+        self.curr_step = AccountStep::View;
+        self.ask_uuid = false;
     }
 
     pub fn show(
@@ -102,24 +185,31 @@ impl Account {
         state: &mut MainState,
         cfg: &mut LauncherSettings,
     ) {
+        self.sync_session(cfg);
         if self.account_type.is_none() {
-            self.show_list(ui, res);
+            self.show_list(ui, res, cfg, state);
         } else {
             let account_type = self.account_type.as_ref().unwrap();
             match self.curr_step {
                 AccountStep::SelectFace => self.select_face_view(ui, cfg, state),
-                AccountStep::Loading => todo!(),
+                AccountStep::Loading => self.show_loading(ui, cfg, state),
                 AccountStep::View => match account_type {
-                    AccountType::Mojang => {}
+                    AccountType::Mojang => self.show_remote(ui, cfg, state),
                     AccountType::Minecraft => {}
-                    AccountType::Local => self.show_local(ui, cfg),
+                    AccountType::Local => self.show_local(ui, cfg, state),
                 },
                 _ => {}
             }
         }
     }
 
-    fn show_list(&mut self, ui: &mut Ui, res: &ResourceLoader) {
+    fn show_list(
+        &mut self,
+        ui: &mut Ui,
+        res: &ResourceLoader,
+        cfg: &mut LauncherSettings,
+        state: &mut MainState,
+    ) {
         ui.vertical_centered(|ui| {
             ui.heading(RichText::new("Choose your Login Type").size(40.).strong());
             ui.add_space(50.);
@@ -130,12 +220,10 @@ impl Account {
                 )
                 .on_hover_text_at_pointer("Not Implemented!");
             ui.add_space(10.);
-            let ms_btn = ui
-                .add(
-                    ImageButton::new(res.ms_btn.texture_id(ui.ctx()), (250., 80.))
-                        .set_enabled(false),
-                )
-                .on_hover_text_at_pointer("Not Implemented!");
+            let ms_btn = ui.add(ImageButton::new(
+                res.ms_btn.texture_id(ui.ctx()),
+                (250., 80.),
+            ));
             ui.add_space(10.);
             let lc_btn = ui.add(ImageButton::new(
                 res.lc_btn.texture_id(ui.ctx()),
@@ -149,38 +237,201 @@ impl Account {
             }
             if ms_btn.clicked() {
                 log::info!("Microsoft Clicked");
-                self.curr_step = AccountStep::View;
+                self.login = Some(MicrosoftLogin::start(cfg.auth.clone()));
+                self.curr_step = AccountStep::Loading;
                 self.account_type.replace(AccountType::Mojang);
             }
             if lc_btn.clicked() {
                 log::info!("Local Clicked");
+                cfg.add_account(UserSession::default());
+                state.changed_face = true;
                 self.curr_step = AccountStep::View;
                 self.account_type.replace(AccountType::Local);
             }
         });
     }
 
-    fn show_local(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings) {
+    fn show_loading(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings, state: &mut MainState) {
+        let Some(login) = self.login.as_mut() else {
+            self.curr_step = AccountStep::View;
+            return;
+        };
+        while let Ok(msg) = login.rcv.try_recv() {
+            match msg {
+                LoginMessage::Code(code) => login.code = Some(code),
+                LoginMessage::Step(step) => login.step = Some(step),
+                LoginMessage::Failed(e) => {
+                    log::error!("Microsoft login failed: {e}");
+                    login.error = Some(e);
+                }
+                LoginMessage::Done(session) => {
+                    cfg.add_account(session);
+                    state.changed_face = true;
+                    add_toast(
+                        &mut state.toasts,
+                        "Account",
+                        &format!("Logged as {}", cfg.session.name),
+                        OpenMCToastKind::Success,
+                    );
+                    self.login = None;
+                    self.session_id = cfg.session.id.clone();
+                    self.selected_icon = face_icon(&cfg.session.face_img);
+                    self.curr_step = AccountStep::View;
+                    return;
+                }
+            }
+        }
+
+        let mut back = false;
+        ui.vertical_centered(|ui| {
+            ui.heading(RichText::new("Login with Microsoft").size(40.).strong());
+            ui.add_space(30.);
+            if let Some(code) = login.code.as_ref() {
+                ui.label(format!(
+                    "Open {} and enter the code:",
+                    code.verification_uri
+                ));
+                ui.add_space(10.);
+                ui.label(
+                    RichText::new(&code.user_code)
+                        .size(32.)
+                        .monospace()
+                        .strong(),
+                );
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    // center the two buttons
+                    ui.add_space(ui.available_width() / 2. - 100.);
+                    if ui
+                        .add_sized((95., 30.), Button::new("Open Browser"))
+                        .clicked()
+                    {
+                        if let Err(e) = open::that(&code.verification_uri) {
+                            log::error!("Cannot open the browser: {e}");
+                        }
+                    }
+                    if ui.add_sized((95., 30.), Button::new("Copy Code")).clicked() {
+                        ui.output_mut(|o| o.copied_text = code.user_code.clone());
+                    }
+                });
+            }
+            ui.add_space(20.);
+            match login.error.as_ref() {
+                Some(e) => {
+                    ui.label(RichText::new(e).color(Color32::LIGHT_RED));
+                    ui.add_space(10.);
+                    if ui.button("Back").clicked() {
+                        back = true;
+                    }
+                }
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.add_space(ui.available_width() / 2. - 80.);
+                        ui.spinner();
+                        let status = login
+                            .step
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| "Requesting the code".to_string());
+                        ui.label(status);
+                    });
+                    ui.add_space(10.);
+                    if ui.button("Cancel").clicked() {
+                        back = true;
+                    }
+                    ui.ctx().request_repaint_after(Duration::from_millis(250));
+                }
+            }
+        });
+        if back {
+            // dropping the login stops the worker
+            self.login = None;
+            self.account_type = None;
+            self.curr_step = AccountStep::View;
+        }
+    }
+
+    fn show_remote(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings, state: &mut MainState) {
+        ui.vertical(|ui| {
+            ui.style_mut().spacing.item_spacing = (10., 15.).into();
+            self.widget_select_face(ui, cfg);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(&cfg.session.name).size(24.).strong());
+                ui.label(format!("UUID: {}", cfg.session.uuid));
+                ui.label(cfg.session.account_origin().to_string());
+                self.account_actions(ui, cfg, state, "Log out");
+            });
+        });
+    }
+
+    fn account_actions(
+        &mut self,
+        ui: &mut Ui,
+        cfg: &mut LauncherSettings,
+        state: &mut MainState,
+        remove_text: &str,
+    ) {
+        ui.horizontal(|ui| {
+            // center the two buttons
+            ui.add_space(ui.available_width() / 2. - 100.);
+            if ui
+                .add_sized((95., 30.), Button::new("Add Account"))
+                .clicked()
+            {
+                self.account_type = None;
+            }
+            if ui.add_sized((95., 30.), Button::new(remove_text)).clicked() {
+                let id = cfg.session.id.clone();
+                cfg.remove_account(&id);
+                state.changed_face = true;
+            }
+        });
+    }
+
+    fn show_local(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings, state: &mut MainState) {
         ui.vertical(|ui| {
             ui.style_mut().spacing.item_spacing = (10., 15.).into();
             self.widget_select_face(ui, cfg);
             ui.vertical_centered(|ui| {
                 ui.horizontal(|ui| {
                     ui.label("User Name:");
-                    ui.text_edit_singleline(&mut cfg.session.name);
+                    let resp = ui.text_edit_singleline(&mut cfg.session.name);
+                    self.name_edited |= resp.changed();
+                    if resp.lost_focus() && self.name_edited {
+                        self.name_edited = false;
+                        self.ask_uuid = cfg.session.uuid != offline_uuid(&cfg.session.name);
+                    }
                 });
+                ui.label(RichText::new(format!("UUID: {}", cfg.session.uuid)).weak());
+                if self.ask_uuid {
+                    ui.label(
+                        "The name changed, do you want a new UUID for it?\n\
+                        The worlds and offline servers keep the player data by UUID",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.add_space(ui.available_width() / 2. - 100.);
+                        if ui.add_sized((95., 30.), Button::new("New UUID")).clicked() {
+                            cfg.session.uuid = offline_uuid(&cfg.session.name);
+                            cfg.save();
+                            self.ask_uuid = false;
+                        }
+                        if ui.add_sized((95., 30.), Button::new("Keep UUID")).clicked() {
+                            cfg.save();
+                            self.ask_uuid = false;
+                        }
+                    });
+                }
+                self.account_actions(ui, cfg, state, "Remove");
             });
         });
     }
 
     fn widget_select_face(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings) {
         ui.vertical_centered(|ui| {
-            let resp = if cfg.session.face_img.is_empty() {
-                ui.add_sized((200., 200.), Button::new("Add Face"))
-            } else {
-                let icon = self.selected_icon.as_ref().unwrap();
-                ui.add(ImageButton::new(icon.id(ui.ctx()), (200., 200.)))
-                    .on_hover_text_at_pointer("Select Face")
+            let resp = match self.selected_icon.as_ref() {
+                Some(icon) if !cfg.session.face_img.is_empty() => ui
+                    .add(ImageButton::new(icon.id(ui.ctx()), (200., 200.)))
+                    .on_hover_text_at_pointer("Select Face"),
+                _ => ui.add_sized((200., 200.), Button::new("Add Face")),
             };
 
             if resp.clicked() {
@@ -228,17 +479,19 @@ impl Account {
             let selected = selected.borrow();
             if !selected.is_empty() {
                 cfg.session.face_img = selected.clone();
-                self.selected_icon = Some(
-                    Icon::image_from_path(
-                        selected.as_str(),
-                        selected.as_str(),
-                        egui_extras::image::FitTo::Size(50, 50),
-                    )
-                    .unwrap(),
-                );
+                self.selected_icon = face_icon(&selected);
                 state.changed_face = true;
             }
             self.face_grid = grid;
         });
     }
 }
+
+fn face_icon(face_img: &str) -> Option<Icon> {
+    if face_img.is_empty() {
+        return None;
+    }
+    Icon::image_from_path(face_img, face_img, egui_extras::image::FitTo::Size(50, 50))
+        .map_err(|e| log::error!("Cannot load the face {face_img}: {e}"))
+        .ok()
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use eframe::egui::{RichText, ScrollArea, Ui};
+use egui::Layout;
+use log::error;
+
+use crate::{
+    launch::CrashReport,
+    widgets::{Modal, ModalBuilder},
+};
+
+pub static CRASH_MODAL_ID: &str = "__openmc__crash_modal";
+
+/// Modal with the crash report of a game
+pub struct CrashViewer {
+    modal: Modal,
+    report: Option<CrashReport>,
+}
+
+impl Default for CrashViewer {
+    fn default() -> Self {
+        Self {
+            modal: ModalBuilder::default()
+                .set_id(CRASH_MODAL_ID)
+                .set_layout(Layout::top_down(egui::Align::Center))
+                .set_size_percent((0.8, 0.8))
+                .build(),
+            report: None,
+        }
+    }
+}
+
+impl CrashViewer {
+    pub fn open(&mut self, ui: &mut Ui, path: &Path) {
+        match CrashReport::load(path) {
+            Ok(report) => {
+                self.report = Some(report);
+                self.modal.open_modal(ui);
+            }
+            Err(e) => error!("Cannot read the crash report {path:?}: {e}"),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        let Some(report) = self.report.as_ref() else {
+            return;
+        };
+        self.modal.show(ui, |ui| {
+            ui.vertical(|ui| {
+                ui.label(RichText::new("Game Crashed").size(25.).strong());
+                ui.add_space(5.);
+                ui.label(RichText::new(&report.description).size(18.));
+                ui.label(RichText::new(report.error()).color(egui::Color32::LIGHT_RED));
+                ui.add_space(10.);
+                let height = ui.available_height() - 50.;
+                ScrollArea::both()
+                    .max_height(height)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(&report.content).monospace());
+                    });
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    if ui.button("Open File").clicked() {
+                        if let Err(e) = open::that(&report.path) {
+                            error!("Cannot open {:?}: {e}", report.path);
+                        }
+                    }
+                    if ui.button("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = report.content.clone());
+                    }
+                    if ui.button("Close").clicked() {
+                        ui.memory_mut(|mem| mem.close_popup());
+                    }
+                });
+            });
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use eframe::egui::{Button, Grid, ProgressBar, RichText, ScrollArea, Ui};
+
+use crate::{
+    download_svc::{format_bytes, JobStatus},
+    MainState,
+};
+
+fn format_eta(eta: Option<Duration>) -> String {
+    let Some(eta) = eta else {
+        return "-".to_string();
+    };
+    let secs = eta.as_secs();
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Every job of the download queue with its progress
+#[inline]
+pub fn downloads(ui: &mut Ui, state: &mut MainState) {
+    let jobs = state.downloads.jobs();
+    let totals = state.downloads.totals();
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(format!(
+                "{} active, {} at {}/s",
+                totals.jobs,
+                format_bytes(totals.bytes),
+                format_bytes(totals.speed)
+            ))
+            .size(18.),
+        );
+        let finished = jobs.iter().any(|j| !j.status.is_active());
+        if ui
+            .add_enabled(finished, Button::new("Clear Finished"))
+            .clicked()
+        {
+            state.downloads.clear_finished();
+        }
+    });
+    ui.add_space(10.);
+    if jobs.is_empty() {
+        ui.label("No downloads yet");
+        return;
+    }
+    ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            Grid::new("downloads_queue")
+                .num_columns(7)
+                .striped(true)
+                .spacing((15., 10.))
+                .show(ui, |ui| {
+                    for title in ["Name", "Status", "Progress", "Bytes", "Speed", "ETA", ""] {
+                        ui.label(RichText::new(title).strong());
+                    }
+                    ui.end_row();
+                    for job in jobs.iter().rev() {
+                        ui.label(job.name()).on_hover_text(job.kind.to_string());
+                        let status = if job.failures.is_empty() {
+                            job.status.to_string()
+                        } else {
+                            format!("{} ({} failed)", job.status.to_string(), job.failures.len())
+                        };
+                        let status = ui.label(status);
+                        let failures = job
+                            .failures
+                            .iter()
+                            .map(|(file, reason)| format!("{file}: {reason}"))
+                            .collect::<Vec<String>>();
+                        match &job.status {
+                            JobStatus::Failed(e) => {
+                                status.on_hover_text(format!("{e}\n{}", failures.join("\n")));
+                            }
+                            _ if !failures.is_empty() => {
+                                status.on_hover_text(format!(
+                                    "{} retries\n{}",
+                                    job.retries,
+                                    failures.join("\n")
+                                ));
+                            }
+                            _ => {}
+                        }
+                        ui.add(
+                            ProgressBar::new(job.progress())
+                                .desired_width(200.)
+                                .text(format!("{}/{} files", job.files_done, job.files_total)),
+                        );
+                        ui.label(format_bytes(job.bytes));
+                        ui.label(format!("{}/s", format_bytes(job.speed)));
+                        ui.label(format_eta(job.eta()));
+                        ui.horizontal(|ui| {
+                            if !job.status.is_active() {
+                                return;
+                            }
+                            if job.status == JobStatus::Paused {
+                                if ui.button("Resume").clicked() {
+                                    state.downloads.resume(job.id);
+                                }
+                            } else if ui.button("Pause").clicked() {
+                                state.downloads.pause(job.id);
+                            }
+                            if ui.button("Cancel").clicked() {
+                                state.downloads.cancel(job.id);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+}
@@ -5,15 +5,15 @@ use crate::{
     screens::utils::launch_instance,
     settings::LauncherSettings,
     widgets::{CoveredImage, CoveredRatioType, ImageButton},
+    MainState,
 };
 
 #[inline]
-pub fn home(ui: &mut Ui, conf: &mut LauncherSettings, res: &ResourceLoader) {
-    let exists_instance = conf.last_launched.is_some();
+pub fn home(ui: &mut Ui, conf: &mut LauncherSettings, res: &ResourceLoader, state: &mut MainState) {
+    let exists_instance = !conf.instances.is_empty();
     let mut value = conf
-        .last_launched
-        .clone()
-        .map(|l| l.name)
+        .last_instance()
+        .map(|i| i.name.clone())
         .unwrap_or_default();
     ui.with_layout(Layout::bottom_up(eframe::emath::Align::Center), |ui| {
         let max_rect = ui.max_rect();
@@ -38,16 +38,13 @@ pub fn home(ui: &mut Ui, conf: &mut LauncherSettings, res: &ResourceLoader) {
             ImageButton::new(res.btn_bg.texture_id(ui.ctx()), (250., 80.)),
         );
 
-        if let Some(ins) = conf.last_launched.clone() {
-            let instance = if value != ins.name {
-                let instance = conf.instances.iter().find(|f| f.name == value).unwrap();
-                conf.last_launched.replace(instance.clone());
-                instance
-            } else {
-                &ins
-            };
-            if btn_play.clicked() {
-                launch_instance(instance, conf);
+        if !value.is_empty() && conf.last_launched.as_ref() != Some(&value) {
+            conf.last_launched = Some(value);
+        }
+        // the instances are always read from the list, never from an old copy
+        if btn_play.clicked() {
+            if let Some(instance) = conf.last_instance() {
+                launch_instance(instance, conf, state);
             }
         }
     });
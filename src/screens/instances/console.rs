@@ -0,0 +1,137 @@
+use eframe::egui::Ui;
+use egui::{Color32, Label, RichText, ScrollArea, TextEdit, TextStyle, TopBottomPanel};
+use log::error;
+
+use crate::launch::{GameState, LogLevel, Supervisor};
+
+/// Live output of the game launched for an instance
+#[derive(Default)]
+pub struct Console {
+    instance: Option<String>,
+    search: String,
+}
+
+impl Console {
+    pub fn open(&mut self, instance: &str) {
+        self.instance = Some(instance.to_string());
+    }
+
+    pub fn close(&mut self) {
+        self.instance = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.instance.is_some()
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, supervisor: &Supervisor) {
+        let process = self.instance.as_ref().and_then(|i| supervisor.get(i));
+        TopBottomPanel::bottom("instance_console")
+            .resizable(true)
+            .default_height(250.)
+            .show_animated_inside(ui, self.instance.is_some(), |ui| {
+                let Some(name) = self.instance.clone() else {
+                    return;
+                };
+                let mut close = false;
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("Console - {name}")).strong());
+                    let state = process.map(|p| p.state());
+                    if let Some(state) = state {
+                        ui.label(RichText::new(state.to_string()).color(state_color(state)));
+                    } else {
+                        ui.label("Not Running");
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Close").clicked() {
+                            close = true;
+                        }
+                        let running = state == Some(GameState::Running);
+                        if ui.add_enabled(running, egui::Button::new("Kill")).clicked() {
+                            if let Err(e) = supervisor.kill(&name) {
+                                error!("Cannot kill instance '{name}': {e}");
+                            }
+                        }
+                        if ui.button("Copy").clicked() {
+                            let search = self.search.to_lowercase();
+                            let text = process
+                                .map(|p| {
+                                    p.with_logs(|logs| {
+                                        logs.iter()
+                                            .filter(|l| matches(&l.text, &search))
+                                            .map(|l| l.text.as_str())
+                                            .collect::<Vec<&str>>()
+                                            .join("\n")
+                                    })
+                                })
+                                .unwrap_or_default();
+                            ui.output_mut(|o| o.copied_text = text);
+                        }
+                        ui.add(
+                            TextEdit::singleline(&mut self.search)
+                                .hint_text("Search")
+                                .desired_width(200.),
+                        );
+                    });
+                });
+                ui.separator();
+                let search = self.search.to_lowercase();
+                let row_height = ui.text_style_height(&TextStyle::Monospace);
+                if let Some(process) = process {
+                    process.with_logs(|logs| {
+                        // only the lines visible are rendered
+                        let rows = logs
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, l)| matches(&l.text, &search))
+                            .map(|(i, _)| i)
+                            .collect::<Vec<usize>>();
+                        ScrollArea::both()
+                            .auto_shrink([false, false])
+                            .stick_to_bottom(true)
+                            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                                for line in rows[range].iter().map(|i| &logs[*i]) {
+                                    let mut text = RichText::new(&line.text).monospace();
+                                    if let Some(color) = level_color(line.level, line.stderr) {
+                                        text = text.color(color);
+                                    }
+                                    // each line is a single row of the same height
+                                    ui.add(Label::new(text).wrap(false));
+                                }
+                            });
+                    });
+                }
+                if close {
+                    self.close();
+                }
+            });
+    }
+}
+
+/// `search` is already lowercase, the line is compared without allocate a lowercase copy
+fn matches(text: &str, search: &str) -> bool {
+    search.is_empty()
+        || text
+            .as_bytes()
+            .windows(search.len())
+            .any(|w| w.eq_ignore_ascii_case(search.as_bytes()))
+}
+
+fn level_color(level: LogLevel, stderr: bool) -> Option<Color32> {
+    match level {
+        LogLevel::Error => Some(Color32::LIGHT_RED),
+        LogLevel::Warn => Some(Color32::GOLD),
+        LogLevel::Debug => Some(Color32::GRAY),
+        LogLevel::Info => None,
+        LogLevel::Other if stderr => Some(Color32::LIGHT_RED),
+        LogLevel::Other => None,
+    }
+}
+
+fn state_color(state: GameState) -> Color32 {
+    match state {
+        GameState::Running => Color32::LIGHT_GREEN,
+        GameState::Exited(_) => Color32::GRAY,
+        GameState::Crashed(_) => Color32::LIGHT_RED,
+    }
+}
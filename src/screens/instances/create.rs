@@ -4,7 +4,6 @@ use egui::{Color32, Layout, RichText};
 use egui_extras::Size;
 use egui_stylist::StylistState;
 use log::info;
-use mc_downloader::prelude::ClientDownloader;
 
 use crate::{
     data::config_path,
@@ -39,89 +38,29 @@ pub struct CreateInstance {
 }
 
 impl CreateInstance {
-    pub fn new(mc: &ClientDownloader) -> Self {
-        let path_icons = config_path("icons");
-
-        let icons = path_icons
-            .read_dir()
-            .unwrap()
-            .flatten()
-            .filter(|f| f.file_name().to_str().unwrap().ends_with(".png"))
-            .flat_map(|f| {
-                let path = f.path();
-                let path = path.to_str().unwrap();
-                let path = path.to_string();
-                let icon = Icon::image_from_path(
-                    f.file_name().to_str().unwrap(),
-                    path.as_str(),
-                    egui_extras::image::FitTo::Size(80, 80),
-                );
-                match icon {
-                    Ok(icon) => Ok((path, icon)),
-                    Err(e) => Err(e),
-                }
-            })
-            .collect::<Vec<(String, Icon)>>();
+    pub fn new(versions: &[MinecraftVersion]) -> Self {
+        let icons = load_icons();
         let icons_len = icons.len() as u8;
 
-        let versions = mc.get_list_versions();
-
-        let mc_releases = versions
-            .iter()
-            .filter(|v| v.version_type == "release")
-            .map(|v| v.id.clone())
-            .collect();
-        let mc_snapshot = versions
-            .iter()
-            .clone()
-            .filter(|v| &v.version_type == "snapshot")
-            .map(|v| v.id.clone())
-            .collect();
-        let mc_oldbeta = versions
-            .iter()
-            .clone()
-            .filter(|v| &v.version_type == "old_beta")
-            .map(|v| v.id.clone())
-            .collect();
-        let mc_oldalpha = versions
-            .iter()
-            .filter(|v| &v.version_type == "old_alpha")
-            .map(|v| v.id.clone())
-            .collect();
-
         Self {
             icons,
             curr_step: 0,
             max_step: STEPS.len() as u8 - 1,
             steps: Steps::with_steps(STEPS.iter().map(|(n, _, _)| *n).collect()),
-            grid: GridWrappedBuilder::default()
-                .set_enabled(true)
-                .show_search()
-                .set_items((0u8..icons_len).collect::<Vec<u8>>())
-                .set_cell_size((100., 100.))
-                .set_button_text("Custom")
-                .build(),
-            versions: GridWrappedBuilder::default()
-                .show_search()
-                .set_enabled(true)
-                .build(),
-            tabs_versions: Tabs::new(
-                &[
-                    ("Release", (0u8, mc_releases)),
-                    ("Snapshots", (1u8, mc_snapshot)),
-                    ("Old Beta", (2u8, mc_oldbeta)),
-                    ("Old Alpha", (3u8, mc_oldalpha)),
-                ],
-                0,
-                20,
-                Color32::WHITE,
-            ),
+            grid: icons_grid(icons_len),
+            versions: versions_grid(),
+            tabs_versions: version_tabs(versions),
             name: String::new(),
             icon_selected: String::new(),
             version_selected: None,
         }
     }
 
+    /// Replace the versions after a refresh of the manifest
+    pub fn set_versions(&mut self, versions: &[MinecraftVersion]) {
+        self.tabs_versions = version_tabs(versions);
+    }
+
     pub fn reset(&mut self) {
         self.curr_step = 0;
         self.version_selected = None;
@@ -171,6 +110,74 @@ impl CreateInstance {
     }
 }
 
+pub(super) fn load_icons() -> Vec<(String, Icon)> {
+    let path_icons = config_path("icons");
+
+    path_icons
+        .read_dir()
+        .unwrap()
+        .flatten()
+        .filter(|f| f.file_name().to_str().unwrap().ends_with(".png"))
+        .flat_map(|f| {
+            let path = f.path();
+            let path = path.to_str().unwrap();
+            let path = path.to_string();
+            let icon = Icon::image_from_path(
+                f.file_name().to_str().unwrap(),
+                path.as_str(),
+                egui_extras::image::FitTo::Size(80, 80),
+            );
+            match icon {
+                Ok(icon) => Ok((path, icon)),
+                Err(e) => Err(e),
+            }
+        })
+        .collect::<Vec<(String, Icon)>>()
+}
+
+pub(super) fn icons_grid(icons_len: u8) -> GridWrapped<u8> {
+    GridWrappedBuilder::default()
+        .set_enabled(true)
+        .show_search()
+        .set_items((0u8..icons_len).collect::<Vec<u8>>())
+        .set_cell_size((100., 100.))
+        .set_button_text("Custom")
+        .build()
+}
+
+pub(super) fn versions_grid() -> GridWrapped<String> {
+    GridWrappedBuilder::default()
+        .show_search()
+        .set_enabled(true)
+        .build()
+}
+
+pub(super) fn version_tabs(versions: &[MinecraftVersion]) -> Tabs<(u8, Vec<String>)> {
+    let ids = |kind: &str| {
+        versions
+            .iter()
+            .filter(|v| v.get_version_type() == kind)
+            .map(|v| v.get_version_id())
+            .collect::<Vec<String>>()
+    };
+    let mc_releases = ids("release");
+    let mc_snapshot = ids("snapshot");
+    let mc_oldbeta = ids("old_beta");
+    let mc_oldalpha = ids("old_alpha");
+
+    Tabs::new(
+        &[
+            ("Release", (0u8, mc_releases)),
+            ("Snapshots", (1u8, mc_snapshot)),
+            ("Old Beta", (2u8, mc_oldbeta)),
+            ("Old Alpha", (3u8, mc_oldalpha)),
+        ],
+        0,
+        20,
+        Color32::WHITE,
+    )
+}
+
 fn next_prev_btn(
     ui: &mut egui::Ui,
     ctx: &mut CreateInstance,
@@ -241,16 +248,7 @@ fn set_name(data: &mut CreateInstance, _theme: &mut StylistState, ui: &mut egui:
 }
 
 fn validate_name(data: &mut CreateInstance, cfg: &mut LauncherSettings) -> Result<(), String> {
-    if data.name.is_empty() {
-        return Err("The name cannot empty".to_string());
-    }
-    if data.name.len() < 3 {
-        return Err("The name size need more than 5".to_string());
-    }
-    if cfg.instances.iter().any(|i| i.name == data.name) {
-        return Err("An instance of this name already exists".to_string());
-    }
-    Ok(())
+    cfg.validate_instance_name(&data.name)
 }
 
 fn set_icon(data: &mut CreateInstance, theme: &mut StylistState, ui: &mut egui::Ui) {
@@ -258,43 +256,61 @@ fn set_icon(data: &mut CreateInstance, theme: &mut StylistState, ui: &mut egui::
         // Icon
         create_label(ui, "Choose an icon that characterizes your instance");
         ui.add_space(20.);
-        let mut grid = data.grid.clone();
-        let selected = RefCell::new(String::new());
-        grid.show(
+        icon_picker(
             ui,
-            Some(|| {
-                if let Some(icon) = select_icon(theme) {
-                    selected.replace(icon.0);
-                }
-            }),
-            Some(|i: usize, _: &u8, search: &str| {
-                data.icons[i]
-                    .0
-                    .to_lowercase()
-                    .contains(&search.to_lowercase())
-            }),
-            |ui, _, v| {
-                ui.centered_and_justified(|ui| {
-                    ui.image(data.icons[*v as usize].1.id(ui.ctx()), (50., 50.));
-                });
-            },
-            |s: usize| {
-                selected.replace(data.icons[s].0.clone());
-            },
+            theme,
+            &mut data.grid,
+            &data.icons,
+            &mut data.icon_selected,
         );
-        let selected = selected.borrow();
-        if !selected.is_empty() {
-            data.icon_selected = selected.clone();
-            info!("Icon is clicked; Path: {}", data.icon_selected);
-        }
-        data.grid = grid;
     });
 }
+
+pub(super) fn icon_picker(
+    ui: &mut egui::Ui,
+    theme: &mut StylistState,
+    icons_grid: &mut GridWrapped<u8>,
+    icons: &[(String, Icon)],
+    icon_selected: &mut String,
+) {
+    let mut grid = icons_grid.clone();
+    let selected = RefCell::new(String::new());
+    grid.show(
+        ui,
+        Some(|| {
+            if let Some(icon) = select_icon(theme) {
+                selected.replace(icon.0);
+            }
+        }),
+        Some(|i: usize, _: &u8, search: &str| {
+            icons[i].0.to_lowercase().contains(&search.to_lowercase())
+        }),
+        |ui, _, v| {
+            ui.centered_and_justified(|ui| {
+                ui.image(icons[*v as usize].1.id(ui.ctx()), (50., 50.));
+            });
+        },
+        |s: usize| {
+            selected.replace(icons[s].0.clone());
+        },
+    );
+    let selected = selected.borrow();
+    if !selected.is_empty() {
+        *icon_selected = selected.clone();
+        info!("Icon is clicked; Path: {icon_selected}");
+    }
+    *icons_grid = grid;
+}
+
 fn validate_icon(data: &mut CreateInstance, _cfg: &mut LauncherSettings) -> Result<(), String> {
-    if data.icon_selected.is_empty() {
+    validate_icon_path(&data.icon_selected)
+}
+
+pub(super) fn validate_icon_path(icon: &str) -> Result<(), String> {
+    if icon.is_empty() {
         return Err("Please select one icon".to_string());
     }
-    let path = PathBuf::from(&data.icon_selected);
+    let path = PathBuf::from(icon);
     if !path.is_file() && !path.exists() {
         return Err("The icon file not exists".to_string());
     }
@@ -305,44 +321,58 @@ fn set_version(data: &mut CreateInstance, _theme: &mut StylistState, ui: &mut eg
     ui.vertical_centered(|ui| {
         create_label(ui, "Choose an icon that characterizes your instance");
         ui.add_space(10.);
-        let mut grid = data.versions.clone();
-        let (n, tab_content) = data.tabs_versions.show(ui);
-        let selected = RefCell::new(String::new());
-        ui.add_space(20.);
-        grid.set_cell_size((ui.available_width() - 20., 30.))
-            .set_items(tab_content.clone())
-            .show(
-                ui,
-                None::<fn()>,
-                Some(|_: usize, item: &String, search: &str| {
-                    item.to_string()
-                        .to_lowercase()
-                        .contains(&search.to_lowercase())
-                }),
-                |ui, _i, item| {
-                    ui.horizontal(|ui| {
-                        ui.label(item.to_string());
-                    });
-                },
-                |s| {
-                    selected.replace(tab_content[s].clone());
-                },
-            );
-        let selected = selected.borrow();
-        if !selected.is_empty() {
-            data.version_selected = match n {
-                0 => Some(MinecraftVersion::Release(selected.clone())),
-                1 => Some(MinecraftVersion::Snapshot(selected.clone())),
-                2 => Some(MinecraftVersion::OldBeta(selected.clone())),
-                3 => Some(MinecraftVersion::OldAlpha(selected.clone())),
-                _ => None,
-            };
-            info!("Version Selected: {:?}", data.version_selected);
-        }
-        data.versions = grid;
+        version_picker(
+            ui,
+            &mut data.versions,
+            &mut data.tabs_versions,
+            &mut data.version_selected,
+        );
     });
 }
 
+pub(super) fn version_picker(
+    ui: &mut egui::Ui,
+    versions: &mut GridWrapped<String>,
+    tabs_versions: &mut Tabs<(u8, Vec<String>)>,
+    version_selected: &mut Option<MinecraftVersion>,
+) {
+    let mut grid = versions.clone();
+    let (n, tab_content) = tabs_versions.show(ui);
+    let selected = RefCell::new(String::new());
+    ui.add_space(20.);
+    grid.set_cell_size((ui.available_width() - 20., 30.))
+        .set_items(tab_content.clone())
+        .show(
+            ui,
+            None::<fn()>,
+            Some(|_: usize, item: &String, search: &str| {
+                item.to_string()
+                    .to_lowercase()
+                    .contains(&search.to_lowercase())
+            }),
+            |ui, _i, item| {
+                ui.horizontal(|ui| {
+                    ui.label(item.to_string());
+                });
+            },
+            |s| {
+                selected.replace(tab_content[s].clone());
+            },
+        );
+    let selected = selected.borrow();
+    if !selected.is_empty() {
+        *version_selected = match n {
+            0 => Some(MinecraftVersion::Release(selected.clone())),
+            1 => Some(MinecraftVersion::Snapshot(selected.clone())),
+            2 => Some(MinecraftVersion::OldBeta(selected.clone())),
+            3 => Some(MinecraftVersion::OldAlpha(selected.clone())),
+            _ => None,
+        };
+        info!("Version Selected: {:?}", version_selected);
+    }
+    *versions = grid;
+}
+
 fn validate_version(data: &mut CreateInstance, _cfg: &mut LauncherSettings) -> Result<(), String> {
     if data.version_selected.is_none() {
         return Err("Please select one version".to_string());
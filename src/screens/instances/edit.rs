@@ -0,0 +1,287 @@
+use std::path::PathBuf;
+
+use egui::{CollapsingHeader, Layout, RichText};
+use egui_stylist::{StylistFileDialog, StylistState};
+use log::info;
+
+use crate::{
+    resources::icon::Icon,
+    screens::launch_options_form,
+    settings::{LaunchOptions, LauncherInstance, LauncherSettings, MinecraftVersion},
+    widgets::{add_toast, GridWrapped, OpenMCToastKind, Tabs},
+    MainState,
+};
+
+use super::create::{
+    icon_picker, icons_grid, load_icons, validate_icon_path, version_picker, version_tabs,
+    versions_grid,
+};
+
+pub struct EditInstance {
+    original: Option<LauncherInstance>,
+    icons: Vec<(String, Icon)>,
+    grid: GridWrapped<u8>,
+    versions: GridWrapped<String>,
+    tabs_versions: Tabs<(u8, Vec<String>)>,
+    name: String,
+    icon_selected: String,
+    version_selected: Option<MinecraftVersion>,
+    java_path: String,
+    tags: String,
+    options: LaunchOptions,
+    account: Option<String>,
+}
+
+impl EditInstance {
+    pub fn new(versions: &[MinecraftVersion]) -> Self {
+        let icons = load_icons();
+        let icons_len = icons.len() as u8;
+
+        Self {
+            icons,
+            original: None,
+            grid: icons_grid(icons_len),
+            versions: versions_grid(),
+            tabs_versions: version_tabs(versions),
+            name: String::new(),
+            icon_selected: String::new(),
+            version_selected: None,
+            java_path: String::new(),
+            tags: String::new(),
+            options: LaunchOptions::default(),
+            account: None,
+        }
+    }
+
+    /// Replace the versions after a refresh of the manifest
+    pub fn set_versions(&mut self, versions: &[MinecraftVersion]) {
+        self.tabs_versions = version_tabs(versions);
+    }
+
+    /// Fill the form with the current values of the instance
+    pub fn open(&mut self, instance: &LauncherInstance) {
+        self.original = Some(instance.clone());
+        self.name = instance.name.clone();
+        self.icon_selected = String::new();
+        self.version_selected = instance.version.clone();
+        self.java_path = instance.java_path.clone();
+        self.tags = instance.tags.join(", ");
+        self.options = instance.options.clone();
+        self.account = instance.account.clone();
+        self.grid.reset();
+        self.versions.reset();
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        theme: &mut StylistState,
+        cfg: &mut LauncherSettings,
+        state: &mut MainState,
+    ) {
+        let Some(original) = self.original.clone() else {
+            state.edit_instance = false;
+            return;
+        };
+        ui.add_space(20.);
+        let height = ui.available_height() - 60.;
+        egui::ScrollArea::vertical()
+            .max_height(height)
+            .show(ui, |ui| {
+                egui::Grid::new("edit_instance_form")
+                    .num_columns(2)
+                    .spacing((20., 15.))
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut self.name);
+                        ui.end_row();
+
+                        ui.label("Version:");
+                        let version = self
+                            .version_selected
+                            .as_ref()
+                            .map(|v| v.to_string())
+                            .unwrap_or_default();
+                        ui.label(version);
+                        ui.end_row();
+
+                        ui.label("Tags:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.tags)
+                                .hint_text("modded, survival"),
+                        )
+                        .on_hover_text("Separated by commas");
+                        ui.end_row();
+
+                        ui.label("Java:");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.java_path)
+                                    .hint_text("Detect automatically"),
+                            );
+                            if ui.button("Browse").clicked() {
+                                if let Some(path) = theme.file_dialog(StylistFileDialog::Open, None)
+                                {
+                                    self.java_path = path.to_str().unwrap().to_string();
+                                }
+                            }
+                            if ui.button("Detect").clicked() {
+                                if let Some(v) = self.version_selected.as_ref() {
+                                    self.java_path =
+                                        cfg.allocate_java(&v.get_version_id()).unwrap_or_default();
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Account:");
+                        let selected = self
+                            .account
+                            .as_deref()
+                            .and_then(|id| cfg.account(id))
+                            .map(|a| a.name.clone())
+                            .unwrap_or_else(|| "Active account".to_string());
+                        egui::ComboBox::from_id_source("edit_instance_account")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.account, None, "Active account");
+                                for account in cfg.all_accounts() {
+                                    ui.selectable_value(
+                                        &mut self.account,
+                                        Some(account.id.clone()),
+                                        format!("{} ({})", account.name, account.origin),
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text("The instance always launches with this account");
+                        ui.end_row();
+                    });
+
+                ui.add_space(20.);
+                CollapsingHeader::new(RichText::new("Launch Options").size(20.))
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        launch_options_form(
+                            ui,
+                            "edit_instance_launch_options",
+                            &mut self.options,
+                            Some(&cfg.launch_defaults),
+                        );
+                    });
+                CollapsingHeader::new(RichText::new("Change Icon").size(20.))
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        ui.set_min_height(300.);
+                        icon_picker(
+                            ui,
+                            theme,
+                            &mut self.grid,
+                            &self.icons,
+                            &mut self.icon_selected,
+                        );
+                    });
+                CollapsingHeader::new(RichText::new("Change Version").size(20.))
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        ui.set_min_height(300.);
+                        version_picker(
+                            ui,
+                            &mut self.versions,
+                            &mut self.tabs_versions,
+                            &mut self.version_selected,
+                        );
+                    });
+            });
+
+        ui.add_space(10.);
+        ui.with_layout(Layout::right_to_left(egui::Align::Min), |ui| {
+            let size = egui::Vec2::new(70., 40.);
+            let save_btn =
+                ui.add(egui::Button::new(RichText::new("Save").size(20.)).min_size(size));
+            let cancel_btn =
+                ui.add(egui::Button::new(RichText::new("Cancel").size(20.)).min_size(size));
+            if save_btn.clicked() {
+                match self
+                    .validate(&original, cfg)
+                    .and_then(|_| self.apply(&original, cfg))
+                {
+                    Ok(_) => {
+                        state.sub_title = String::new();
+                        state.edit_instance = false;
+                        self.original = None;
+                    }
+                    Err(e) => add_toast(
+                        &mut state.toasts,
+                        "Invalid Input",
+                        e.as_str(),
+                        OpenMCToastKind::Error,
+                    ),
+                }
+            }
+            if cancel_btn.clicked() {
+                state.sub_title = String::new();
+                state.edit_instance = false;
+                self.original = None;
+            }
+        });
+    }
+
+    /// Same rules used by the steps of `CreateInstance`
+    fn validate(&self, original: &LauncherInstance, cfg: &LauncherSettings) -> Result<(), String> {
+        if self.name != original.name {
+            cfg.validate_instance_name(&self.name)?;
+        }
+        if !self.icon_selected.is_empty() {
+            validate_icon_path(&self.icon_selected)?;
+        }
+        if self.version_selected.is_none() {
+            return Err("Please select one version".to_string());
+        }
+        if !self.java_path.is_empty() && !PathBuf::from(&self.java_path).is_file() {
+            return Err("The java binary not exists".to_string());
+        }
+        self.options.inherit(&cfg.launch_defaults).validate()
+    }
+
+    fn apply(&self, original: &LauncherInstance, cfg: &mut LauncherSettings) -> Result<(), String> {
+        if self.name != original.name {
+            cfg.rename_instance(&original.name, &self.name)?;
+        }
+        let Some(mut instance) = cfg.instances.iter().find(|i| i.name == self.name).cloned() else {
+            return Err(format!("Instance '{}' not exists", self.name));
+        };
+
+        if !self.icon_selected.is_empty() {
+            let mut icon_path = PathBuf::from(&instance.path);
+            icon_path.push("icon.png");
+            std::fs::copy(&self.icon_selected, icon_path).map_err(|e| e.to_string())?;
+        }
+        instance.java_path = self.java_path.clone();
+        if self.version_selected != instance.version {
+            instance.version = self.version_selected.clone();
+            instance.downloaded = false;
+            // the java of the old version may not work with the new one
+            if self.java_path == original.java_path {
+                instance.java_path = self
+                    .version_selected
+                    .as_ref()
+                    .and_then(|v| cfg.allocate_java(&v.get_version_id()))
+                    .unwrap_or_default();
+            }
+        }
+        instance.options = self.options.clone();
+        instance.account = self.account.clone();
+        instance.tags = Vec::new();
+        for tag in self.tags.split(',').map(|t| t.trim()) {
+            if !tag.is_empty() && !instance.tags.iter().any(|t| t == tag) {
+                instance.tags.push(tag.to_string());
+            }
+        }
+
+        cfg.update_instance(&instance);
+        cfg.save();
+        info!("Instance '{}' edited", instance.name);
+        Ok(())
+    }
+}
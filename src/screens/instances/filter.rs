@@ -0,0 +1,164 @@
+use std::cmp::Ordering;
+
+use crate::settings::LauncherInstance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceSort {
+    Name,
+    Version,
+    RecentlyPlayed,
+}
+
+impl InstanceSort {
+    pub const ALL: [InstanceSort; 3] = [
+        InstanceSort::Name,
+        InstanceSort::Version,
+        InstanceSort::RecentlyPlayed,
+    ];
+
+    pub fn compare(&self, a: &LauncherInstance, b: &LauncherInstance) -> Ordering {
+        match self {
+            InstanceSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            // the newest version first
+            InstanceSort::Version => version_key(b).cmp(&version_key(a)),
+            InstanceSort::RecentlyPlayed => b.last_played.cmp(&a.last_played),
+        }
+    }
+}
+
+impl ToString for InstanceSort {
+    fn to_string(&self) -> String {
+        match self {
+            InstanceSort::Name => "Name".to_string(),
+            InstanceSort::Version => "Version".to_string(),
+            InstanceSort::RecentlyPlayed => "Recently Played".to_string(),
+        }
+    }
+}
+
+/// `1.20.1` is `[1, 20, 1]`, `23w14a` is `[23, 14]`
+fn version_key(instance: &LauncherInstance) -> Vec<u32> {
+    instance
+        .version
+        .as_ref()
+        .map(|v| v.get_version_id())
+        .unwrap_or_default()
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|p| p.parse().ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceGroup {
+    None,
+    Tag,
+    VersionType,
+}
+
+impl InstanceGroup {
+    pub const ALL: [InstanceGroup; 3] = [
+        InstanceGroup::None,
+        InstanceGroup::Tag,
+        InstanceGroup::VersionType,
+    ];
+
+    /// Title of the sections where the instance is shown
+    fn keys(&self, instance: &LauncherInstance) -> Vec<String> {
+        match self {
+            InstanceGroup::None => vec![String::new()],
+            InstanceGroup::Tag if instance.tags.is_empty() => vec!["Untagged".to_string()],
+            InstanceGroup::Tag => instance.tags.clone(),
+            InstanceGroup::VersionType => vec![instance
+                .version
+                .as_ref()
+                .map(|v| v.get_version_type())
+                .unwrap_or_else(|| "No Version".to_string())],
+        }
+    }
+
+    /// Split the sorted instances on sections, an instance with many tags is on each section.
+    /// Returns the items and the start index with the title of each section
+    pub fn apply(
+        &self,
+        instances: Vec<LauncherInstance>,
+    ) -> (Vec<LauncherInstance>, Vec<(usize, String)>) {
+        if *self == InstanceGroup::None {
+            return (instances, Vec::new());
+        }
+        let mut groups: Vec<(String, Vec<LauncherInstance>)> = Vec::new();
+        for instance in instances {
+            for key in self.keys(&instance) {
+                match groups.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, items)) => items.push(instance.clone()),
+                    None => groups.push((key, vec![instance.clone()])),
+                }
+            }
+        }
+        groups.sort_by(|(a, _), (b, _)| a.to_lowercase().cmp(&b.to_lowercase()));
+
+        let mut items = Vec::new();
+        let mut sections = Vec::new();
+        for (key, group) in groups {
+            sections.push((items.len(), key));
+            items.extend(group);
+        }
+        (items, sections)
+    }
+}
+
+impl ToString for InstanceGroup {
+    fn to_string(&self) -> String {
+        match self {
+            InstanceGroup::None => "None".to_string(),
+            InstanceGroup::Tag => "Tag".to_string(),
+            InstanceGroup::VersionType => "Version Type".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum QueryTerm {
+    Tag(String),
+    Version(String),
+    Type(String),
+    Name(String),
+}
+
+/// Search of instances, ex: `tag:modded version:1.20 survival`.
+/// Every term must match, the terms without prefix are searched on the name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceQuery {
+    terms: Vec<QueryTerm>,
+}
+
+impl InstanceQuery {
+    pub fn parse(query: &str) -> Self {
+        let terms = query
+            .split_whitespace()
+            .map(|t| {
+                let t = t.to_lowercase();
+                match t.split_once(':') {
+                    Some(("tag", v)) => QueryTerm::Tag(v.to_string()),
+                    Some(("version", v)) => QueryTerm::Version(v.to_string()),
+                    Some(("type", v)) => QueryTerm::Type(v.to_string()),
+                    _ => QueryTerm::Name(t),
+                }
+            })
+            .collect();
+        Self { terms }
+    }
+
+    pub fn matches(&self, instance: &LauncherInstance) -> bool {
+        let version = instance.version.as_ref();
+        self.terms.iter().all(|term| match term {
+            QueryTerm::Tag(tag) => instance.tags.iter().any(|t| t.to_lowercase() == *tag),
+            QueryTerm::Version(v) => version
+                .map(|version| version.get_version_id().starts_with(v.as_str()))
+                .unwrap_or(false),
+            QueryTerm::Type(kind) => version
+                .map(|version| version.get_version_type() == *kind)
+                .unwrap_or(false),
+            QueryTerm::Name(name) => instance.name.to_lowercase().contains(name.as_str()),
+        })
+    }
+}
@@ -1,30 +1,32 @@
+mod console;
 mod create;
+mod edit;
+mod filter;
 pub mod utils;
 
-use std::{
-    cell::RefCell,
-    sync::{mpsc::Receiver, Arc, Mutex},
-};
+use std::cell::RefCell;
 
+pub use console::*;
 pub use create::*;
+pub use edit::*;
+pub use filter::*;
 
 use eframe::egui::Ui;
-use egui::{Button, Color32, Layout, RichText, SidePanel, Vec2};
+use egui::{
+    Button, CollapsingHeader, Color32, ComboBox, Layout, RichText, SidePanel, TextEdit, Vec2,
+};
 use egui_extras::image::FitTo;
-use egui_toast::Toasts;
-use log::{debug, info};
-use mc_downloader::prelude::{ClientDownloader, DownloadVersion};
+use log::{error, info};
 
 use crate::{
-    data::data_path,
-    download_svc::{DownloadProgress, DownloadProgressMessage},
+    download_svc::{JobKind, JobStatus},
     resources::icon::Icon,
     settings::{LauncherInstance, LauncherSettings},
     widgets::{add_toast, GridWrapped, GridWrappedBuilder, IconButton, ProgressButton},
     MainState,
 };
 
-use self::utils::launch_instance;
+use self::utils::{format_elapsed, format_playtime, launch_instance};
 
 pub struct Instances {
     selected: RefCell<Option<LauncherInstance>>,
@@ -32,17 +34,22 @@ pub struct Instances {
     widget: GridWrapped<LauncherInstance>,
     download_button: ProgressButton,
     icon_close: Icon,
-    progress: DownloadProgress,
-    progress_rcv: Receiver<DownloadProgressMessage>,
+    console: Console,
+    /// Instance launched meanwhile its session is validated, the console opens when it starts
+    console_pending: Option<String>,
+    sort: InstanceSort,
+    group: InstanceGroup,
+    query: String,
 }
 
 impl Default for Instances {
     fn default() -> Self {
-        let (progress, progress_rcv) = DownloadProgress::new();
-
         Self {
-            progress,
-            progress_rcv,
+            console: Console::default(),
+            console_pending: None,
+            sort: InstanceSort::Name,
+            group: InstanceGroup::None,
+            query: String::new(),
             selected: RefCell::new(None),
             frame_sizes: RefCell::new(Vec::new()),
             download_button: ProgressButton::default()
@@ -51,7 +58,6 @@ impl Default for Instances {
                 .set_progress(0.)
                 .build(),
             widget: GridWrappedBuilder::default()
-                .show_search()
                 .set_cell_size((200., 200.))
                 .set_button_text("Create Instance")
                 .build(),
@@ -67,12 +73,35 @@ impl Instances {
         ui: &mut Ui,
         cfg: &mut LauncherSettings,
         widget: &mut CreateInstance,
+        edit_widget: &mut EditInstance,
         state: &mut MainState,
     ) {
         if !ui.is_rect_visible(ui.max_rect()) {
             return;
         }
         ui.add_space(20.);
+        if let Some(name) = self.console_pending.clone() {
+            if state.supervisor.is_running(&name) {
+                self.console.open(&name);
+                self.console_pending = None;
+            } else if !state.sessions.is_validating(&name) {
+                self.console_pending = None;
+            }
+        }
+        self.console.show(ui, &state.supervisor);
+        if self.toolbar(ui) {
+            self.widget.reset();
+        }
+        let query = InstanceQuery::parse(&self.query);
+        let mut items = cfg
+            .instances
+            .iter()
+            .filter(|i| query.matches(i))
+            .cloned()
+            .collect::<Vec<LauncherInstance>>();
+        items.sort_by(|a, b| self.sort.compare(a, b));
+        let (items, sections) = self.group.apply(items);
+        let items_len = items.len();
         ui.with_layout(Layout::left_to_right(egui::Align::Min), |ui| {
             let mut grid = self.widget.clone();
             let mut replaced = false;
@@ -86,7 +115,8 @@ impl Instances {
                 ui.set_max_width(ui.available_width());
             }
             grid.set_enabled(grid_enabled)
-                .set_items(cfg.instances.clone())
+                .set_items(items.clone())
+                .set_sections(sections)
                 .show(
                     ui,
                     Some(|| {
@@ -94,13 +124,11 @@ impl Instances {
                         reset = true;
                         state.create_instance = true;
                     }),
-                    Some(|_: usize, item: &LauncherInstance, search: &str| {
-                        item.name.to_lowercase().contains(&search.to_lowercase())
-                    }),
+                    None::<fn(usize, &LauncherInstance, &str) -> bool>,
                     |ui, i, item| {
                         let mut frame_sizes = self.frame_sizes.borrow_mut();
-                        if frame_sizes.len() != cfg.instances.len() {
-                            frame_sizes.resize(cfg.instances.len(), Vec2::default());
+                        if frame_sizes.len() != items_len {
+                            frame_sizes.resize(items_len, Vec2::default());
                         }
                         ui.horizontal_centered(|ui| {
                             ui.add_space((ui.available_width() - frame_sizes[i].x) / 2.0);
@@ -127,10 +155,10 @@ impl Instances {
                     },
                     |s: usize| {
                         replaced = true;
-                        self.selected.replace(Some(cfg.instances[s].clone()));
+                        self.selected.replace(Some(items[s].clone()));
                     },
                 );
-            self.info_section(ui, cfg, &mut state.toasts, &mut grid);
+            self.info_section(ui, cfg, edit_widget, state, &mut grid);
             let launch_btn = if reset || replaced {
                 self.download_button.set_progress(0.).clone()
             } else {
@@ -144,12 +172,46 @@ impl Instances {
         });
     }
 
+    /// Search, sort and group of the grid, returns true when something changed
+    fn toolbar(&mut self, ui: &mut Ui) -> bool {
+        let (sort, group, query) = (self.sort, self.group, self.query.clone());
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.add(
+                TextEdit::singleline(&mut self.query)
+                    .hint_text("tag:modded version:1.20 type:release")
+                    .desired_width(300.),
+            )
+            .on_hover_text("Filter by name, tag:, version: or type:");
+            ui.add_space(10.);
+            ui.label("Sort by:");
+            ComboBox::from_id_source("instances_sort")
+                .selected_text(self.sort.to_string())
+                .show_ui(ui, |ui| {
+                    for sort in InstanceSort::ALL {
+                        ui.selectable_value(&mut self.sort, sort, sort.to_string());
+                    }
+                });
+            ui.add_space(10.);
+            ui.label("Group by:");
+            ComboBox::from_id_source("instances_group")
+                .selected_text(self.group.to_string())
+                .show_ui(ui, |ui| {
+                    for group in InstanceGroup::ALL {
+                        ui.selectable_value(&mut self.group, group, group.to_string());
+                    }
+                });
+        });
+        sort != self.sort || group != self.group || query != self.query
+    }
+
     #[inline]
     fn info_section(
         &mut self,
         ui: &mut Ui,
         cfg: &mut LauncherSettings,
-        toasts: &mut Toasts,
+        edit_widget: &mut EditInstance,
+        state: &mut MainState,
         grid: &mut GridWrapped<LauncherInstance>,
     ) {
         let binding = self.selected.clone();
@@ -193,94 +255,181 @@ impl Instances {
                             ui.add_space(10.);
                             ui.label(version.get_version_id());
                         }
+                        if !instance.tags.is_empty() {
+                            ui.add_space(5.);
+                            ui.label(RichText::new(instance.tags.join(", ")).italics());
+                        }
+                        ui.add_space(10.);
+                        let last_played = instance
+                            .last_played
+                            .map(format_elapsed)
+                            .unwrap_or_else(|| "Never".to_string());
+                        ui.label(format!("Last played: {last_played}"));
+                        ui.label(format!(
+                            "Playtime: {}",
+                            format_playtime(instance.total_playtime)
+                        ));
                         ui.add_space(20.);
                         // Buttons
-                        self.action_buttons(ui, cfg, toasts);
+                        if self.action_buttons(ui, cfg, edit_widget, state) {
+                            self.selected.replace(None);
+                            grid.reset();
+                        }
                         ui.add_space(20.);
+                        CollapsingHeader::new("Launch History")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                if instance.history.is_empty() {
+                                    ui.label("Not launched yet");
+                                }
+                                for record in instance.history.iter().rev() {
+                                    let code = record
+                                        .exit_code
+                                        .map(|c| c.to_string())
+                                        .unwrap_or_else(|| "-".to_string());
+                                    ui.label(format!(
+                                        "{} - {} - exit code {code}",
+                                        format_elapsed(record.started),
+                                        format_playtime(record.duration),
+                                    ));
+                                }
+                            });
                     }
                 });
             });
     }
 
+    /// Returns true when the side panel must be closed
     #[inline]
-    fn action_buttons(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings, toasts: &mut Toasts) {
+    fn action_buttons(
+        &mut self,
+        ui: &mut Ui,
+        cfg: &mut LauncherSettings,
+        edit_widget: &mut EditInstance,
+        state: &mut MainState,
+    ) -> bool {
+        let mut close = false;
+        let mut edit = false;
         let mut binding = self.selected.borrow_mut();
         if binding.is_none() {
-            return;
+            return close;
         }
         let mut_instance = binding.as_mut().unwrap();
+        // the downloads finish on the main application, keep the copy updated
+        if let Some(instance) = cfg.instances.iter().find(|i| i.name == mut_instance.name) {
+            *mut_instance = instance.clone();
+        }
+        let job = state.downloads.job_of(&mut_instance.name);
+        mut_instance.downloading = job.is_some();
         // Buttons
         ui.horizontal(|ui| {
             // progress for downloading
-            if mut_instance.downloading {
-                while let Ok(msg) = self.progress_rcv.try_recv() {
-                    match msg {
-                        DownloadProgressMessage::Setup(_) => add_toast(
-                            toasts,
-                            "Instance",
-                            &format!(
-                                "Downloading resources for instance: {}",
-                                mut_instance.name.clone()
-                            ),
-                            crate::widgets::OpenMCToastKind::Info,
-                        ),
-                        DownloadProgressMessage::Update(curr, max) => {
-                            ui.ctx().request_repaint();
-                            self.download_button
-                                .set_progress(curr as f32 / max as f32)
-                                .set_text("Downloading...")
-                                .build();
-                        }
-                        DownloadProgressMessage::End => {
-                            debug!("Message Downloaded!!");
-                            mut_instance.downloading = false;
-                            mut_instance.downloaded = true;
-                            self.download_button
-                                .set_progress(0.)
-                                .set_text("Launch")
-                                .build();
-                            add_toast(
-                                toasts,
+            if let Some(job) = job.as_ref() {
+                let text = match (job.kind, &job.status) {
+                    (_, JobStatus::Queued) => "Queued...",
+                    (_, JobStatus::Paused) => "Paused",
+                    (JobKind::Verify, _) => "Verifying...",
+                    (JobKind::Download, _) => "Downloading...",
+                };
+                self.download_button
+                    .set_progress(job.progress())
+                    .set_text(text)
+                    .build();
+            } else if mut_instance.downloaded {
+                self.download_button.set_progress(0.).set_text("Launch");
+            } else {
+                self.download_button.set_progress(0.);
+            }
+            // Launch
+            let width = ui.available_width() - 10.;
+            let running = state.supervisor.is_running(&mut_instance.name);
+            let validating = state.sessions.is_validating(&mut_instance.name);
+            if mut_instance.missing {
+                self.download_button.set_text("Folder not found");
+            } else if running {
+                self.download_button.set_text("Running...");
+            } else if validating {
+                self.download_button.set_text("Checking account...");
+            }
+            let enabled =
+                !mut_instance.downloading && !running && !validating && !mut_instance.missing;
+            ui.add_enabled_ui(enabled, |ui| {
+                let btn = ui.add_sized(Vec2::new(width, 50.), self.download_button.clone());
+                if btn.clicked() {
+                    if !mut_instance.downloaded {
+                        match state.downloads.enqueue(mut_instance, JobKind::Download) {
+                            Ok(_) => add_toast(
+                                &mut state.toasts,
                                 "Instance",
                                 &format!(
-                                    "Resources for instance are Downloaded: {}",
+                                    "Downloading resources for instance: {}",
                                     mut_instance.name.clone()
                                 ),
                                 crate::widgets::OpenMCToastKind::Info,
-                            );
+                            ),
+                            Err(e) => {
+                                error!("Cannot download '{}': {e}", mut_instance.name);
+                                add_toast(
+                                    &mut state.toasts,
+                                    "Instance",
+                                    &e,
+                                    crate::widgets::OpenMCToastKind::Error,
+                                );
+                            }
                         }
+                    } else {
+                        launch_instance(mut_instance, cfg, state);
+                        self.console_pending = Some(mut_instance.name.clone());
                     }
                 }
-            }
-            if mut_instance.downloaded {
-                self.download_button.set_progress(0.).set_text("Launch");
-            }
-            // Launch
-            let width = ui.available_width() - 10.;
-            ui.add_enabled_ui(!mut_instance.downloading, |ui| {
-                let btn = ui.add_sized(Vec2::new(width, 50.), self.download_button.clone());
-                if btn.clicked() {
-                    if !mut_instance.downloaded && !mut_instance.downloading {
-                        let progress = self.progress.clone();
-                        let v = mut_instance.version.clone().unwrap();
-                        std::thread::spawn(move || {
-                            debug!("creating thread and start download");
-                            ClientDownloader::new()
-                                .unwrap()
-                                .download_version(
-                                    &v.get_version_id(),
-                                    data_path("").to_str().unwrap(),
-                                    Some(Arc::new(Mutex::new(progress))),
-                                )
-                                .unwrap();
-                            debug!("Downloaded");
-                        });
-                        debug!("Downloading");
-                        mut_instance.downloading = true;
-                        self.download_button.set_text("Downloading...");
+            });
+        });
+        if let Some(job) = job.as_ref() {
+            ui.add_space(10.);
+            ui.horizontal(|ui| {
+                let width = ui.available_width() / 2. - 10.;
+                if job.status == JobStatus::Paused {
+                    let resume_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Resume"));
+                    if resume_btn.clicked() {
+                        state.downloads.resume(job.id);
                     }
-                    if mut_instance.downloaded {
-                        launch_instance(mut_instance, cfg);
+                } else {
+                    let pause_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Pause"));
+                    if pause_btn.clicked() {
+                        state.downloads.pause(job.id);
+                    }
+                }
+                let cancel_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Cancel"));
+                if cancel_btn.clicked() {
+                    state.downloads.cancel(job.id);
+                }
+            });
+        }
+        ui.add_space(10.);
+        ui.horizontal(|ui| {
+            let width = ui.available_width() / 2. - 10.;
+            let has_output = state.supervisor.get(&mut_instance.name).is_some();
+            ui.add_enabled_ui(has_output, |ui| {
+                let console_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Console"));
+                if console_btn.clicked() {
+                    self.console.open(&mut_instance.name);
+                }
+            });
+            let can_verify = mut_instance.downloaded
+                && !mut_instance.downloading
+                && !state.supervisor.is_running(&mut_instance.name);
+            ui.add_enabled_ui(can_verify, |ui| {
+                let verify_btn = ui
+                    .add_sized(Vec2::new(width, 30.), Button::new("Verify Files"))
+                    .on_hover_text("Check the files and download again the broken ones");
+                if verify_btn.clicked() {
+                    if let Err(e) = state.downloads.enqueue(mut_instance, JobKind::Verify) {
+                        add_toast(
+                            &mut state.toasts,
+                            "Verify Files",
+                            &e,
+                            crate::widgets::OpenMCToastKind::Error,
+                        );
                     }
                 }
             });
@@ -290,20 +439,32 @@ impl Instances {
         ui.horizontal(|ui| {
             let width = ui.available_width() / 3. - 10.;
 
-            ui.add_enabled_ui(false, |ui| {
-                ui.add_sized(Vec2::new(width, 30.), Button::new("Edit").wrap(true));
+            // the folder of a running or launching instance cannot be moved
+            let running = state.supervisor.is_running(&mut_instance.name);
+            let validating = state.sessions.is_validating(&mut_instance.name);
+            let can_edit =
+                !mut_instance.downloading && !running && !validating && !mut_instance.missing;
+            ui.add_enabled_ui(can_edit, |ui| {
+                let edit_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Edit").wrap(true));
+                if edit_btn.clicked() {
+                    edit_widget.open(mut_instance);
+                    edit = true;
+                    close = true;
+                }
             });
-            let open_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Open").wrap(true));
-            if open_btn.clicked() {
-                open::that(mut_instance.path.clone()).unwrap();
-            }
-            ui.add_enabled_ui(!mut_instance.downloading, |ui| {
+            ui.add_enabled_ui(!mut_instance.missing, |ui| {
+                let open_btn = ui.add_sized(Vec2::new(width, 30.), Button::new("Open").wrap(true));
+                if open_btn.clicked() {
+                    open::that(mut_instance.path.clone()).unwrap();
+                }
+            });
+            ui.add_enabled_ui(!mut_instance.downloading && !running, |ui| {
                 let delete_btn = ui.add_sized(
                     Vec2::new(width, 30.),
                     Button::new("Delete").wrap(true).fill(Color32::LIGHT_RED),
                 );
                 if delete_btn.clicked() {
-                    self.selected.replace(None);
+                    close = true;
                     cfg.remove_instance(mut_instance.name.clone());
                 }
             });
@@ -313,5 +474,10 @@ impl Instances {
                 *i = mut_instance.clone();
             }
         });
+        if edit {
+            state.sub_title = "Edit Instance".to_string();
+            state.edit_instance = true;
+        }
+        close
     }
 }
@@ -1,26 +1,77 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use egui_stylist::{StylistFileDialog, StylistState};
-use log::debug;
-use mc_bootstrap::ClientBootstrap;
+use log::{debug, error};
 
 use crate::{
-    data::data_path,
     resources::icon::Icon,
-    settings::{LauncherInstance, LauncherSettings},
+    settings::{AccountOrigin, LauncherInstance, LauncherSettings},
+    widgets::{add_toast, OpenMCToastKind},
+    MainState,
 };
 
-pub fn launch_instance(instance: &LauncherInstance, cfg: &LauncherSettings) {
-    let v = instance.version.clone().unwrap();
-    ClientBootstrap::new(
-        &cfg.session.access_token,
-        data_path("").to_str().unwrap(),
-        &instance.java_path,
-        &cfg.session.name,
-        &cfg.session.uuid,
-        &v.get_version_id(),
-        &v.get_version_type(),
-    )
-    .launch()
-    .unwrap();
+/// Launch the instance once its session is validated,
+/// the remote accounts are checked on background
+pub fn launch_instance(instance: &LauncherInstance, cfg: &LauncherSettings, state: &mut MainState) {
+    let session = match cfg.session_for(instance) {
+        Ok(session) => session,
+        Err(e) => {
+            add_toast(
+                &mut state.toasts,
+                "Launch Failed",
+                &e,
+                OpenMCToastKind::Error,
+            );
+            return;
+        }
+    };
+    if session.origin == AccountOrigin::Local {
+        start_instance(instance, cfg, state);
+        return;
+    }
+    state.sessions.validate(&instance.name, session, &cfg.auth);
+}
+
+/// Start the game under the supervisor of the launcher
+pub fn start_instance(instance: &LauncherInstance, cfg: &LauncherSettings, state: &mut MainState) {
+    if let Err(e) = state.supervisor.launch(instance, cfg) {
+        error!("Cannot launch instance '{}': {e}", instance.name);
+        add_toast(
+            &mut state.toasts,
+            "Launch Failed",
+            &e.to_string(),
+            OpenMCToastKind::Error,
+        );
+    }
+}
+
+/// Time played in a short form, ex: `3h 20m`, `45m`, `30s`
+pub fn format_playtime(secs: u64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{secs}s"),
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+/// Time since an unix timestamp, ex: `5 minutes ago`, `3 days ago`
+pub fn format_elapsed(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let secs = now.saturating_sub(timestamp);
+    let (value, unit) = match secs {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s if s < 86400 * 30 => (s / 86400, "day"),
+        s if s < 86400 * 365 => (s / (86400 * 30), "month"),
+        s => (s / (86400 * 365), "year"),
+    };
+    let plural = if value == 1 { "" } else { "s" };
+    format!("{value} {unit}{plural} ago")
 }
 
 pub fn select_icon(state: &mut StylistState) -> Option<(String, Icon)> {
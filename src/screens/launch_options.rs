@@ -0,0 +1,149 @@
+use eframe::egui::{DragValue, Grid, TextEdit, Ui};
+
+use crate::settings::LaunchOptions;
+
+/// Form of the launch options, when `defaults` is given each option can override the inherited value
+pub fn launch_options_form(
+    ui: &mut Ui,
+    id: &str,
+    options: &mut LaunchOptions,
+    defaults: Option<&LaunchOptions>,
+) {
+    Grid::new(id)
+        .num_columns(3)
+        .spacing((20., 10.))
+        .show(ui, |ui| {
+            option_row(
+                ui,
+                "Min Memory (MB):",
+                &mut options.min_memory,
+                defaults.map(|d| &d.min_memory),
+                1024,
+                |ui, v| {
+                    ui.add(DragValue::new(v).speed(64).clamp_range(256..=65536));
+                },
+            );
+            option_row(
+                ui,
+                "Max Memory (MB):",
+                &mut options.max_memory,
+                defaults.map(|d| &d.max_memory),
+                2048,
+                |ui, v| {
+                    ui.add(DragValue::new(v).speed(64).clamp_range(256..=65536));
+                },
+            );
+            option_row(
+                ui,
+                "JVM Arguments:",
+                &mut options.jvm_args,
+                defaults.map(|d| &d.jvm_args),
+                String::new(),
+                |ui, v| {
+                    ui.add(TextEdit::singleline(v).hint_text("-XX:+UseG1GC"));
+                },
+            );
+            option_row(
+                ui,
+                "Game Arguments:",
+                &mut options.game_args,
+                defaults.map(|d| &d.game_args),
+                String::new(),
+                |ui, v| {
+                    ui.text_edit_singleline(v);
+                },
+            );
+            option_row(
+                ui,
+                "Resolution:",
+                &mut options.resolution,
+                defaults.map(|d| &d.resolution),
+                (854, 480),
+                |ui, (width, height)| {
+                    ui.horizontal(|ui| {
+                        ui.add(DragValue::new(width).clamp_range(1..=7680));
+                        ui.label("x");
+                        ui.add(DragValue::new(height).clamp_range(1..=4320));
+                    });
+                },
+            );
+            option_row(
+                ui,
+                "Fullscreen:",
+                &mut options.fullscreen,
+                defaults.map(|d| &d.fullscreen),
+                false,
+                |ui, v| {
+                    ui.checkbox(v, "");
+                },
+            );
+            option_row(
+                ui,
+                "Environment:",
+                &mut options.env,
+                defaults.map(|d| &d.env),
+                Vec::new(),
+                env_editor,
+            );
+        });
+}
+
+/// `fallback` is the value when the option is enabled without an inherited value
+fn option_row<T: Clone>(
+    ui: &mut Ui,
+    label: &str,
+    value: &mut Option<T>,
+    inherited: Option<&Option<T>>,
+    fallback: T,
+    editor: impl FnOnce(&mut Ui, &mut T),
+) {
+    ui.label(label);
+    let mut enabled = value.is_some();
+    let text = if inherited.is_some() {
+        "Override"
+    } else {
+        "Enabled"
+    };
+    if ui.checkbox(&mut enabled, text).changed() {
+        *value = enabled.then(|| inherited.cloned().flatten().unwrap_or(fallback.clone()));
+    }
+    match value.as_mut() {
+        Some(v) => editor(ui, v),
+        None => {
+            // show the value that will be used without let edit it
+            let mut shown = inherited.cloned().flatten().unwrap_or(fallback);
+            ui.add_enabled_ui(false, |ui| editor(ui, &mut shown));
+        }
+    }
+    ui.end_row();
+}
+
+fn env_editor(ui: &mut Ui, env: &mut Vec<(String, String)>) {
+    ui.vertical(|ui| {
+        let mut remove = None;
+        for (i, (key, value)) in env.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(key)
+                        .hint_text("NAME")
+                        .desired_width(120.),
+                );
+                ui.label("=");
+                ui.add(
+                    TextEdit::singleline(value)
+                        .hint_text("value")
+                        .desired_width(160.),
+                );
+                if ui.small_button("x").on_hover_text("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            env.remove(i);
+        }
+        if ui.button("Add Variable").clicked() {
+            env.push((String::new(), String::new()));
+        }
+    });
+}
@@ -1,19 +1,30 @@
 use eframe::egui::{RichText, Ui};
 
 mod account;
+mod crash;
+mod downloads;
 mod home;
 mod instances;
+mod launch_options;
 mod preferences;
+mod recovery;
+mod secrets;
 
 pub use self::preferences::*;
 pub use account::*;
+pub use crash::*;
+pub use downloads::*;
 pub use home::*;
 pub use instances::*;
+pub use launch_options::*;
+pub use recovery::*;
+pub use secrets::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ViewType {
     Home,
     Instances,
+    Downloads,
     Preferences,
 }
 
@@ -28,6 +39,11 @@ pub fn tab_buttons(ui: &mut Ui, view: &mut ViewType) {
             ViewType::Instances,
             RichText::new("Instances").size(32.),
         );
+        ui.selectable_value(
+            view,
+            ViewType::Downloads,
+            RichText::new("Downloads").size(32.),
+        );
         ui.selectable_value(
             view,
             ViewType::Preferences,
@@ -1,12 +1,15 @@
-use eframe::egui::Ui;
+use eframe::egui::{CollapsingHeader, Color32, ComboBox, RichText, Ui};
 use egui_stylist::StylistState;
 use log::trace;
 
 use crate::{
     data::theme::{load_theme, save_theme},
-    settings::LauncherSettings,
+    secrets::SecretBackend,
+    settings::{LaunchOptions, LauncherSettings},
 };
 
+use super::launch_options_form;
+
 pub fn preferences(ui: &mut Ui, theme: &mut StylistState, conf: &mut LauncherSettings) {
     ui.vertical(|ui| {
         ui.horizontal(|ui| {
@@ -21,6 +24,45 @@ pub fn preferences(ui: &mut Ui, theme: &mut StylistState, conf: &mut LauncherSet
             }
         });
         ui.add_space(10.);
+        CollapsingHeader::new(RichText::new("Default Launch Options").size(20.))
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.label("Used by the instances that not override them");
+                ui.add_space(5.);
+                // the changes are only applied when they are valid
+                let draft_id = ui.id().with("launch_defaults_draft");
+                let mut draft = ui
+                    .data_mut(|d| d.get_temp::<LaunchOptions>(draft_id))
+                    .unwrap_or_else(|| conf.launch_defaults.clone());
+                launch_options_form(ui, "launch_defaults_form", &mut draft, None);
+                match draft.validate() {
+                    Ok(_) => conf.launch_defaults = draft.clone(),
+                    Err(e) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                    }
+                }
+                ui.data_mut(|d| d.insert_temp(draft_id, draft));
+            });
+        ui.add_space(10.);
+        CollapsingHeader::new(RichText::new("Accounts").size(20.))
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Save the tokens on:");
+                    let mut backend = conf.secret_backend();
+                    ComboBox::from_id_source("secret_backend")
+                        .selected_text(backend.to_string())
+                        .show_ui(ui, |ui| {
+                            for b in [SecretBackend::SecretService, SecretBackend::EncryptedFile] {
+                                ui.selectable_value(&mut backend, b, b.to_string());
+                            }
+                        });
+                    if backend != conf.secret_backend() {
+                        conf.set_secret_backend(backend);
+                    }
+                });
+            });
+        ui.add_space(10.);
         theme.ui(ui);
     });
 }
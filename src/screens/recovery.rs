@@ -0,0 +1,50 @@
+use eframe::egui::{RichText, Ui};
+use egui::Color32;
+
+use crate::settings::{LauncherSettings, SettingsError};
+
+/// Shown instead of the launcher when the settings file cannot be loaded,
+/// returns the settings once the user choose how recover them
+pub fn settings_recovery(
+    ui: &mut Ui,
+    frame: &mut eframe::Frame,
+    error: &SettingsError,
+) -> Option<Result<LauncherSettings, SettingsError>> {
+    let mut res = None;
+    ui.vertical_centered(|ui| {
+        ui.add_space(100.);
+        ui.heading(
+            RichText::new("Cannot load your settings")
+                .size(40.)
+                .strong(),
+        );
+        ui.add_space(20.);
+        ui.label(RichText::new(error.to_string()).color(Color32::LIGHT_RED));
+        ui.add_space(20.);
+        ui.label("Your instances files are safe, choose how you want to continue");
+        ui.add_space(30.);
+
+        let size = (300., 40.);
+        let restore_btn = ui.add_enabled(
+            LauncherSettings::has_backup(),
+            egui::Button::new(RichText::new("Restore Last Backup").size(20.)).min_size(size.into()),
+        );
+        if restore_btn.clicked() {
+            res = Some(LauncherSettings::restore_backup());
+        }
+        ui.add_space(10.);
+        let reset_btn = ui
+            .add(egui::Button::new(RichText::new("Reset Settings").size(20.)).min_size(size.into()))
+            .on_hover_text("The broken file is kept as launcher.conf.broken");
+        if reset_btn.clicked() {
+            res = Some(LauncherSettings::reset());
+        }
+        ui.add_space(10.);
+        let quit_btn =
+            ui.add(egui::Button::new(RichText::new("Quit").size(20.)).min_size(size.into()));
+        if quit_btn.clicked() {
+            frame.close();
+        }
+    });
+    res
+}
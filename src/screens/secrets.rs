@@ -0,0 +1,147 @@
+use std::{
+    sync::{
+        mpsc::{Receiver, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
+
+use eframe::egui::{RichText, Ui};
+use egui::{Color32, Key, TextEdit};
+
+use crate::{
+    secrets::{open_store, EncryptedFileStore, SecretBackend, SecretStore},
+    settings::LauncherSettings,
+};
+
+type OpenResult = Result<Arc<dyn SecretStore>, String>;
+
+/// Shown instead of the launcher until the tokens of the saved remote accounts are unlocked
+#[derive(Default)]
+pub struct UnlockSecrets {
+    passphrase: String,
+    error: Option<String>,
+    /// Backend that the user chose to not unlock
+    skipped: Option<SecretBackend>,
+    /// The keyring is tried once without ask the user
+    tried: Option<SecretBackend>,
+    /// The keyring and the key derivation can take a while, they run on background
+    opening: Option<(SecretBackend, Receiver<OpenResult>)>,
+}
+
+impl UnlockSecrets {
+    pub fn is_pending(&self, cfg: &LauncherSettings) -> bool {
+        cfg.needs_secrets() && self.skipped != Some(cfg.secret_backend())
+    }
+
+    fn open(&mut self, backend: SecretBackend, passphrase: Option<String>) {
+        let (sender, rcv) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let res = open_store(backend, passphrase.as_deref()).map_err(|e| e.to_string());
+            let _ = sender.send(res);
+        });
+        self.error = None;
+        self.opening = Some((backend, rcv));
+    }
+
+    fn poll(&mut self, cfg: &mut LauncherSettings) {
+        let Some((backend, rcv)) = self.opening.as_ref() else {
+            return;
+        };
+        let res = match rcv.try_recv() {
+            Ok(res) => res,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err("The store stopped".to_string()),
+        };
+        let backend = *backend;
+        self.opening = None;
+        match res {
+            // the user may switch the backend meanwhile
+            Ok(store) if backend == cfg.secret_backend() => cfg.attach_secrets(store),
+            Ok(_) => {}
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, cfg: &mut LauncherSettings) {
+        self.poll(cfg);
+        let backend = cfg.secret_backend();
+        if !backend.needs_passphrase() && self.tried != Some(backend) && self.opening.is_none() {
+            self.tried = Some(backend);
+            self.open(backend, None);
+        }
+
+        ui.vertical_centered(|ui| {
+            ui.add_space(100.);
+            ui.heading(RichText::new("Unlock your accounts").size(40.).strong());
+            ui.add_space(20.);
+            if self.opening.is_some() {
+                ui.spinner();
+                ui.label(format!("Opening the {backend}..."));
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+            if let Some(e) = self.error.as_ref() {
+                ui.label(RichText::new(e).color(Color32::LIGHT_RED));
+                ui.add_space(20.);
+            }
+
+            let size = (300., 40.);
+            if backend.needs_passphrase() {
+                ui.label(if EncryptedFileStore::exists() {
+                    "Enter the passphrase of your saved accounts"
+                } else {
+                    "Create a passphrase to protect your saved accounts"
+                });
+                ui.add_space(10.);
+                let resp = ui.add(
+                    TextEdit::singleline(&mut self.passphrase)
+                        .password(true)
+                        .desired_width(size.0),
+                );
+                let enter = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                ui.add_space(30.);
+                let unlock_btn = ui.add(
+                    egui::Button::new(RichText::new("Unlock").size(20.)).min_size(size.into()),
+                );
+                if unlock_btn.clicked() || enter {
+                    let passphrase = std::mem::take(&mut self.passphrase);
+                    self.open(backend, Some(passphrase));
+                }
+                ui.add_space(10.);
+                let keyring_btn = ui.add(
+                    egui::Button::new(RichText::new("Use the System Keyring").size(20.))
+                        .min_size(size.into()),
+                );
+                if keyring_btn.clicked() {
+                    self.error = None;
+                    cfg.set_secret_backend(SecretBackend::SecretService);
+                }
+            } else {
+                let retry_btn = ui
+                    .add(egui::Button::new(RichText::new("Retry").size(20.)).min_size(size.into()));
+                if retry_btn.clicked() {
+                    self.tried = None;
+                }
+                ui.add_space(10.);
+                let file_btn = ui
+                    .add(
+                        egui::Button::new(RichText::new("Use an Encrypted File").size(20.))
+                            .min_size(size.into()),
+                    )
+                    .on_hover_text("The tokens are encrypted with a passphrase");
+                if file_btn.clicked() {
+                    self.error = None;
+                    cfg.set_secret_backend(SecretBackend::EncryptedFile);
+                }
+            }
+            ui.add_space(10.);
+            let skip_btn = ui
+                .add(egui::Button::new(RichText::new("Skip").size(20.)).min_size(size.into()))
+                .on_hover_text("The Microsoft accounts need to log in again");
+            if skip_btn.clicked() {
+                self.skipped = Some(backend);
+            }
+        });
+    }
+}
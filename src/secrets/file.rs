@@ -0,0 +1,116 @@
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use log::{debug, info};
+
+use crate::data::config_path;
+
+use super::SecretStore;
+
+pub static SECRETS_FILE: &str = "secrets.bin";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// All the secrets on a single file, `salt | nonce | encrypted json`,
+/// the key is derived from the passphrase with argon2
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    salt: [u8; SALT_LEN],
+    cipher: XChaCha20Poly1305,
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl EncryptedFileStore {
+    pub fn exists() -> bool {
+        config_path("").join(SECRETS_FILE).is_file()
+    }
+
+    /// Decrypt the file with the passphrase, a new file uses the passphrase from now on
+    pub fn open(passphrase: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(anyhow!("The passphrase cannot be empty"));
+        }
+        let path = config_path("").join(SECRETS_FILE);
+        if !path.is_file() {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            info!("New secrets file at {path:?}");
+            return Ok(Self {
+                cipher: derive_cipher(passphrase, &salt)?,
+                path,
+                salt,
+                secrets: Mutex::default(),
+            });
+        }
+
+        let content = std::fs::read(&path)?;
+        if content.len() < SALT_LEN + NONCE_LEN {
+            return Err(anyhow!("The secrets file is broken"));
+        }
+        let (salt, content) = content.split_at(SALT_LEN);
+        let (nonce, encrypted) = content.split_at(NONCE_LEN);
+        let salt: [u8; SALT_LEN] = salt.try_into()?;
+        let cipher = derive_cipher(passphrase, &salt)?;
+        let decrypted = cipher
+            .decrypt(XNonce::from_slice(nonce), encrypted)
+            .map_err(|_| anyhow!("Wrong passphrase"))?;
+        let secrets = serde_json::from_slice(&decrypted)?;
+        debug!("Secrets file unlocked");
+        Ok(Self {
+            path,
+            salt,
+            cipher,
+            secrets: Mutex::new(secrets),
+        })
+    }
+
+    /// Encrypt with a new nonce and replace the file only when the write finish
+    fn write(&self, secrets: &HashMap<String, String>) -> Result<()> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let encrypted = self
+            .cipher
+            .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+            .map_err(|_| anyhow!("Cannot encrypt the secrets"))?;
+        let mut content = Vec::with_capacity(SALT_LEN + NONCE_LEN + encrypted.len());
+        content.extend_from_slice(&self.salt);
+        content.extend_from_slice(&nonce);
+        content.extend_from_slice(&encrypted);
+        let tmp_path = self.path.with_extension("bin.tmp");
+        std::fs::write(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+fn derive_cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Cannot derive the key: {e}"))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.secrets.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+        secrets.insert(key.to_string(), secret.to_string());
+        self.write(&secrets)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+        if secrets.remove(key).is_some() {
+            self.write(&secrets)?;
+        }
+        Ok(())
+    }
+}
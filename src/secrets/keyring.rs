@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use log::debug;
+use secret_service::{blocking::SecretService, EncryptionType};
+
+use crate::data::APP_NAME;
+
+use super::SecretStore;
+
+/// Attribute used to find the items of the launcher on the keyring
+static APP_ATTRIBUTE: &str = "application";
+static KEY_ATTRIBUTE: &str = "key";
+
+/// The connection is opened on each operation, the tokens change only on login
+pub struct SecretServiceStore;
+
+impl SecretServiceStore {
+    /// Check that the service is running and the default collection can be unlocked
+    pub fn connect() -> Result<Self> {
+        let service = SecretService::connect(EncryptionType::Dh)?;
+        service.get_default_collection()?.ensure_unlocked()?;
+        debug!("Connected to the Secret Service");
+        Ok(Self)
+    }
+
+    fn attributes(key: &str) -> HashMap<&str, &str> {
+        HashMap::from([(APP_ATTRIBUTE, APP_NAME), (KEY_ATTRIBUTE, key)])
+    }
+}
+
+impl SecretStore for SecretServiceStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        let service = SecretService::connect(EncryptionType::Dh)?;
+        let collection = service.get_default_collection()?;
+        collection.ensure_unlocked()?;
+        let Some(item) = collection
+            .search_items(Self::attributes(key))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        Ok(Some(String::from_utf8(item.get_secret()?)?))
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<()> {
+        let service = SecretService::connect(EncryptionType::Dh)?;
+        let collection = service.get_default_collection()?;
+        collection.ensure_unlocked()?;
+        collection.create_item(
+            &format!("{APP_NAME} ({key})"),
+            Self::attributes(key),
+            secret.as_bytes(),
+            true,
+            "text/plain",
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let service = SecretService::connect(EncryptionType::Dh)?;
+        let collection = service.get_default_collection()?;
+        collection.ensure_unlocked()?;
+        for item in collection.search_items(Self::attributes(key))? {
+            item.delete()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+mod file;
+#[cfg(target_os = "linux")]
+mod keyring;
+
+use std::{fmt::Display, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+pub use file::*;
+#[cfg(target_os = "linux")]
+pub use keyring::*;
+
+/// Place where the tokens of the accounts are saved, the settings only keep the key
+pub trait SecretStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&self, key: &str, secret: &str) -> Result<()>;
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecretBackend {
+    /// Keyring of the desktop through the freedesktop Secret Service
+    SecretService,
+    /// File encrypted with a passphrase of the user
+    EncryptedFile,
+}
+
+impl Display for SecretBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretBackend::SecretService => write!(f, "System Keyring"),
+            SecretBackend::EncryptedFile => write!(f, "Encrypted File"),
+        }
+    }
+}
+
+impl Default for SecretBackend {
+    fn default() -> Self {
+        if cfg!(target_os = "linux") {
+            SecretBackend::SecretService
+        } else {
+            SecretBackend::EncryptedFile
+        }
+    }
+}
+
+impl SecretBackend {
+    pub fn needs_passphrase(&self) -> bool {
+        *self == SecretBackend::EncryptedFile
+    }
+}
+
+pub fn open_store(
+    backend: SecretBackend,
+    passphrase: Option<&str>,
+) -> Result<Arc<dyn SecretStore>> {
+    match backend {
+        #[cfg(target_os = "linux")]
+        SecretBackend::SecretService => Ok(Arc::new(SecretServiceStore::connect()?)),
+        #[cfg(not(target_os = "linux"))]
+        SecretBackend::SecretService => Err(anyhow!(
+            "The Secret Service is not available on this system"
+        )),
+        SecretBackend::EncryptedFile => {
+            let passphrase = passphrase.ok_or_else(|| anyhow!("The passphrase is required"))?;
+            Ok(Arc::new(EncryptedFileStore::open(passphrase)?))
+        }
+    }
+}
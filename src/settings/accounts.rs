@@ -0,0 +1,357 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{error, info, warn};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::secrets::{open_store, SecretBackend, SecretStore};
+
+use super::{AccountOrigin, LauncherInstance, LauncherSettings, UserSession};
+
+/// Unique id of the saved accounts, used to pin an account to the instances
+pub fn new_account_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{nanos:x}")
+}
+
+/// Same uuid that vanilla gives to the players of offline servers,
+/// a v3 uuid of `OfflinePlayer:{name}`
+pub fn offline_uuid(name: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{name}")).into();
+    hash[6] = hash[6] & 0x0f | 0x30;
+    hash[8] = hash[8] & 0x3f | 0x80;
+    hex::encode(hash)
+}
+
+/// Key of the tokens of the account on the secret store
+pub(super) fn secret_key(id: &str) -> String {
+    format!("account-{id}")
+}
+
+/// Content saved on the secret store for each remote account
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSecret {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+impl From<&UserSession> for SessionSecret {
+    fn from(session: &UserSession) -> Self {
+        Self {
+            access_token: session.access_token.clone(),
+            refresh_token: session.refresh_token.clone(),
+        }
+    }
+}
+
+impl SessionSecret {
+    fn apply(self, session: &mut UserSession) {
+        session.access_token = self.access_token;
+        session.refresh_token = self.refresh_token;
+    }
+}
+
+/// Seconds before the expiration when the token is already considered expired
+const EXPIRATION_MARGIN: u64 = 300;
+
+impl UserSession {
+    /// The local accounts never expire, the remote ones without expiration
+    /// must be validated with the services
+    pub fn is_expired(&self) -> bool {
+        if self.origin == AccountOrigin::Local {
+            return false;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.expires_at
+            .map(|t| t <= now + EXPIRATION_MARGIN)
+            .unwrap_or(false)
+    }
+
+    /// The remote accounts keep its place meanwhile the secrets are locked
+    fn is_saved(&self) -> bool {
+        self.is_logged() || self.secret.is_some()
+    }
+
+    fn has_tokens(&self) -> bool {
+        self.access_token != "null" && !self.access_token.is_empty()
+    }
+
+    /// Both sessions are the same remote account
+    fn same_account(&self, other: &UserSession) -> bool {
+        self.origin != AccountOrigin::Local
+            && self.origin == other.origin
+            && self.uuid == other.uuid
+    }
+}
+
+impl LauncherSettings {
+    /// The active account first and then the other saved accounts
+    pub fn all_accounts(&self) -> impl Iterator<Item = &UserSession> {
+        std::iter::once(&self.session).chain(self.accounts.iter())
+    }
+
+    pub fn account(&self, id: &str) -> Option<&UserSession> {
+        self.all_accounts().find(|a| a.id == id)
+    }
+
+    /// The account pinned to the instance or the active one
+    pub fn session_for(&self, instance: &LauncherInstance) -> Result<&UserSession, String> {
+        match instance.account.as_deref() {
+            Some(id) => self.account(id).ok_or_else(|| {
+                format!(
+                    "The account pinned to instance '{}' not exists",
+                    instance.name
+                )
+            }),
+            None => Ok(&self.session),
+        }
+    }
+
+    /// Make the account active, the previous one is kept on the saved accounts
+    pub fn switch_account(&mut self, id: &str) -> bool {
+        let Some(pos) = self.accounts.iter().position(|a| a.id == id) else {
+            return false;
+        };
+        let account = self.accounts.remove(pos);
+        let prev = std::mem::replace(&mut self.session, account);
+        if prev.is_saved() {
+            self.accounts.push(prev);
+        }
+        info!("Switched to account {}", self.session.name);
+        self.save();
+        true
+    }
+
+    /// Save a new account as the active one, a login of an account already saved
+    /// only replaces its tokens
+    pub fn add_account(&mut self, mut session: UserSession) {
+        if let Some(account) = self.all_accounts().find(|a| a.same_account(&session)) {
+            session.id = account.id.clone();
+            session.face_img = account.face_img.clone();
+            let id = session.id.clone();
+            self.update_account(session);
+            self.switch_account(&id);
+            return;
+        }
+        self.store_secret(&mut session);
+        let prev = std::mem::replace(&mut self.session, session);
+        if prev.is_saved() {
+            self.accounts.push(prev);
+        }
+        info!("Account {} added", self.session.name);
+        self.save();
+    }
+
+    /// Replace the tokens of a saved account without change the active one
+    pub fn update_account(&mut self, mut session: UserSession) {
+        self.store_secret(&mut session);
+        let Some(account) = std::iter::once(&mut self.session)
+            .chain(self.accounts.iter_mut())
+            .find(|a| a.id == session.id)
+        else {
+            warn!("Cannot update the account {}, it not exists", session.name);
+            return;
+        };
+        *account = session;
+        self.save();
+    }
+
+    /// Forget the account, when it is the active one the next saved account is used
+    pub fn remove_account(&mut self, id: &str) {
+        self.legacy_secrets.remove(id);
+        if let (Some(store), Some(key)) = (
+            self.secrets.as_ref(),
+            self.account(id).and_then(|a| a.secret.as_ref()),
+        ) {
+            if let Err(e) = store.delete(key) {
+                error!("Cannot delete the secret of the account: {e}");
+            }
+        }
+        if self.session.id == id {
+            self.session = if self.accounts.is_empty() {
+                UserSession::default()
+            } else {
+                self.accounts.remove(0)
+            };
+        } else {
+            self.accounts.retain(|a| a.id != id);
+        }
+        // the instances pinned to the account go back to the active one
+        let pinned = self
+            .instances
+            .iter()
+            .filter(|i| i.account.as_deref() == Some(id))
+            .cloned()
+            .collect::<Vec<LauncherInstance>>();
+        for mut instance in pinned {
+            warn!("Account unpinned from instance '{}'", instance.name);
+            instance.account = None;
+            self.update_instance(&instance);
+        }
+        self.save();
+    }
+
+    pub fn secrets_unlocked(&self) -> bool {
+        self.secrets.is_some()
+    }
+
+    /// Only the saved remote accounts need the store, the local accounts work without it
+    pub fn needs_secrets(&self) -> bool {
+        !self.secrets_unlocked()
+            && self
+                .all_accounts()
+                .any(|a| a.origin != AccountOrigin::Local && a.secret.is_some())
+    }
+
+    /// Backend chosen by the user, it is only saved when the new store has the tokens
+    pub fn secret_backend(&self) -> SecretBackend {
+        self.next_backend.unwrap_or(self.secret_backend)
+    }
+
+    /// Open the secret store and use it, blocks until the store answers
+    pub fn unlock_secrets(&mut self, passphrase: Option<&str>) -> Result<(), String> {
+        let store = open_store(self.secret_backend(), passphrase).map_err(|e| e.to_string())?;
+        self.attach_secrets(store);
+        Ok(())
+    }
+
+    /// Load the tokens of the remote accounts from the store, the tokens already in memory
+    /// or from old settings are moved into the store
+    pub fn attach_secrets(&mut self, store: Arc<dyn SecretStore>) {
+        let mut all_saved = true;
+        for session in std::iter::once(&mut self.session).chain(self.accounts.iter_mut()) {
+            if session.origin == AccountOrigin::Local {
+                continue;
+            }
+            let key = session
+                .secret
+                .get_or_insert_with(|| secret_key(&session.id))
+                .clone();
+            let legacy = self.legacy_secrets.get(&session.id).cloned();
+            if let Some(secret) = legacy {
+                secret.apply(session);
+            }
+            let res = if session.has_tokens() {
+                serde_json::to_string(&SessionSecret::from(&*session))
+                    .map_err(anyhow::Error::from)
+                    .and_then(|secret| store.set(&key, &secret))
+            } else {
+                store.get(&key).and_then(|secret| {
+                    if let Some(secret) = secret {
+                        serde_json::from_str::<SessionSecret>(&secret)?.apply(session);
+                    }
+                    Ok(())
+                })
+            };
+            match res {
+                // the old tokens leave the settings only when the store has them
+                Ok(_) => {
+                    self.legacy_secrets.remove(&session.id);
+                }
+                Err(e) => {
+                    // the new tokens are only kept on memory until exit
+                    error!("Cannot load the secret of {}: {e}", session.name);
+                    all_saved = false;
+                }
+            }
+        }
+        self.secrets = Some(store);
+        if let Some(backend) = self.next_backend.take() {
+            self.secret_backend = backend;
+        }
+        // the old store is cleaned only when the new one has all the tokens
+        if let Some(old) = self.retired_secrets.take() {
+            if all_saved {
+                for key in self.all_accounts().filter_map(|a| a.secret.as_ref()) {
+                    if let Err(e) = old.delete(key) {
+                        warn!("Cannot delete the secret from the old store: {e}");
+                    }
+                }
+            } else {
+                warn!("Some tokens were not moved, the old store keeps them");
+            }
+        }
+        info!("Secrets unlocked with {}", self.secret_backend.to_string());
+        self.save();
+    }
+
+    /// Move the secrets to other backend, the tokens stay on the old store
+    /// until the new store is unlocked
+    pub fn set_secret_backend(&mut self, backend: SecretBackend) {
+        if backend == self.secret_backend() {
+            return;
+        }
+        if backend == self.secret_backend {
+            // back to the store that still has the tokens
+            self.next_backend = None;
+            if let Some(old) = self.retired_secrets.take() {
+                self.secrets = Some(old);
+            }
+            return;
+        }
+        if let Some(store) = self.secrets.take() {
+            self.retired_secrets.get_or_insert(store);
+        }
+        self.next_backend = Some(backend);
+        if !self.needs_secrets() {
+            // nothing to move
+            self.secret_backend = backend;
+            self.next_backend = None;
+            self.retired_secrets = None;
+            self.save();
+        }
+    }
+
+    /// Write the tokens of the account into the secret store
+    fn store_secret(&self, session: &mut UserSession) {
+        if session.origin == AccountOrigin::Local {
+            return;
+        }
+        let key = session
+            .secret
+            .get_or_insert_with(|| secret_key(&session.id))
+            .clone();
+        let Some(store) = self.secrets.as_ref() else {
+            warn!(
+                "The secrets are locked, the tokens of {} are only kept until exit",
+                session.name
+            );
+            return;
+        };
+        let res = serde_json::to_string(&SessionSecret::from(&*session))
+            .map_err(anyhow::Error::from)
+            .and_then(|secret| store.set(&key, &secret));
+        if let Err(e) = res {
+            error!("Cannot save the secret of {}: {e}", session.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::offline_uuid;
+
+    #[test]
+    fn vanilla_offline_uuid() {
+        // UUID.nameUUIDFromBytes("OfflinePlayer:Notch") of vanilla
+        assert_eq!(offline_uuid("Notch"), "b50ad385829d3141a2167e7d7539ba7f");
+        assert_eq!(offline_uuid("jeb_"), "a762f5604fce3236812ab80efff0b62b");
+    }
+
+    #[test]
+    fn offline_uuid_is_v3() {
+        let uuid = offline_uuid("Steve");
+        assert_eq!(uuid.len(), 32);
+        assert_eq!(&uuid[12..13], "3");
+        assert!(matches!(&uuid[16..17], "8" | "9" | "a" | "b"));
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The settings file not exists yet
+    Missing,
+    /// The file cannot be decrypted, ex: it was saved with another key
+    Decrypt(String),
+    /// The content is decrypted but it not match with the settings
+    Parse(String),
+    /// The settings cannot be written in the format of the file
+    Serialize(String),
+    Io(std::io::Error),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Missing => write!(f, "The settings file not exists"),
+            SettingsError::Decrypt(e) => write!(f, "Cannot decrypt the settings file: {e}"),
+            SettingsError::Parse(e) => write!(f, "Cannot read the settings: {e}"),
+            SettingsError::Serialize(e) => write!(f, "Cannot write the settings: {e}"),
+            SettingsError::Io(e) => write!(f, "Cannot access the settings file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(e: std::io::Error) -> Self {
+        SettingsError::Io(e)
+    }
+}
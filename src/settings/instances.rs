@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, error, info, warn};
+
+use crate::data::data_path;
+
+use super::{LaunchRecord, LauncherInstance, SettingsError};
+
+pub static INSTANCE_MANIFEST: &str = "instance.toml";
+/// Max launches kept on the history of each instance
+pub const MAX_LAUNCH_HISTORY: usize = 20;
+
+impl LauncherInstance {
+    pub fn manifest_path(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.path);
+        path.push(INSTANCE_MANIFEST);
+        path
+    }
+
+    pub fn push_launch(&mut self, record: LaunchRecord) {
+        self.last_played = Some(record.started);
+        self.total_playtime += record.duration;
+        self.history.push(record);
+        if self.history.len() > MAX_LAUNCH_HISTORY {
+            let extra = self.history.len() - MAX_LAUNCH_HISTORY;
+            self.history.drain(..extra);
+        }
+    }
+
+    /// Write the `instance.toml` into the folder of the instance
+    pub fn save_manifest(&self) -> Result<(), SettingsError> {
+        let content =
+            toml::to_string_pretty(self).map_err(|e| SettingsError::Serialize(e.to_string()))?;
+        let path = self.manifest_path();
+        let tmp_path = path.with_extension("toml.tmp");
+        std::fs::write(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, &path)?;
+        debug!("Instance manifest saved: {path:?}");
+        Ok(())
+    }
+
+    /// Read the manifest of an instance folder, the folder name is the name of the instance
+    pub fn load_manifest(folder: PathBuf) -> Result<Self, SettingsError> {
+        let mut path = folder.clone();
+        path.push(INSTANCE_MANIFEST);
+        if !path.is_file() {
+            return Err(SettingsError::Missing);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let instance =
+            toml::from_str::<Self>(&content).map_err(|e| SettingsError::Parse(e.to_string()))?;
+        let name = folder
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+        if instance.name != name {
+            warn!(
+                "Instance manifest {path:?} has the name '{}', using '{name}'",
+                instance.name
+            );
+        }
+
+        Ok(Self {
+            name,
+            path: folder.to_str().unwrap().to_string(),
+            ..instance
+        })
+    }
+}
+
+/// Build the instances list scanning `data_path("instances")`,
+/// the folders with a broken manifest are ignored. The legacy instances with a
+/// folder get their manifest and leave `legacy`, the others are listed as missing
+pub fn load_instances(legacy: &mut Vec<LauncherInstance>) -> Vec<LauncherInstance> {
+    legacy.retain(|instance| !adopt_legacy(instance));
+    let mut instances = match data_path("instances").read_dir() {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| match LauncherInstance::load_manifest(e.path()) {
+                Ok(instance) => Some(instance),
+                Err(SettingsError::Missing) => None,
+                Err(err) => {
+                    warn!("Ignoring instance {:?}: {err}", e.path());
+                    None
+                }
+            })
+            .collect::<Vec<LauncherInstance>>(),
+        Err(_) => Vec::new(),
+    };
+    for instance in legacy.iter() {
+        if !instances.iter().any(|i| i.name == instance.name) {
+            instances.push(LauncherInstance {
+                missing: true,
+                ..instance.clone()
+            });
+        }
+    }
+    instances.sort_by_key(|i| i.name.to_lowercase());
+    debug!("Instances found: {}", instances.len());
+    instances
+}
+
+/// Write the manifest of an instance saved by the settings before v2,
+/// returns true when the instance lives on its folder
+fn adopt_legacy(instance: &LauncherInstance) -> bool {
+    if !Path::new(&instance.path).is_dir() {
+        warn!("The folder of instance '{}' not exists", instance.name);
+        return false;
+    }
+    if instance.manifest_path().exists() {
+        return true;
+    }
+    match instance.save_manifest() {
+        Ok(_) => {
+            info!("Manifest created for instance '{}'", instance.name);
+            true
+        }
+        Err(e) => {
+            error!(
+                "Cannot create the manifest of instance '{}': {e}",
+                instance.name
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy(name: &str, path: PathBuf) -> LauncherInstance {
+        LauncherInstance {
+            name: name.to_string(),
+            path: path.to_str().unwrap().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn legacy_without_folder_is_missing() {
+        let mut pending = vec![legacy(
+            "Lost",
+            PathBuf::from("/nonexistent/openmc/instances/Lost"),
+        )];
+        let instances = load_instances(&mut pending);
+        assert_eq!(pending.len(), 1);
+        let lost = instances.iter().find(|i| i.name == "Lost").unwrap();
+        assert!(lost.missing);
+    }
+
+    #[test]
+    fn legacy_with_folder_gets_manifest() {
+        let folder = std::env::temp_dir().join(format!("openmc-legacy-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let mut pending = vec![legacy("Found", folder.clone())];
+        load_instances(&mut pending);
+        assert!(pending.is_empty());
+        let manifest = LauncherInstance::load_manifest(folder.clone());
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert!(!manifest.unwrap().missing);
+    }
+}
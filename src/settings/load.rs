@@ -1,17 +1,23 @@
-use std::fs::File;
-
-use preferences::security::{SecurePreferences, SecurityManager};
+use preferences::{
+    security::{SecurePreferences, SecurityManager},
+    PreferencesError,
+};
 
 use crate::data::{config_path, OPENMC_SECURE_KEY};
 
-pub fn load_settings<A: SecurePreferences + Default>(file_name: &str) -> A {
+use super::SettingsError;
+
+pub fn load_settings<A: SecurePreferences>(file_name: &str) -> Result<A, SettingsError> {
     let manager = SecurityManager::new(&OPENMC_SECURE_KEY, None);
     let mut conf_dir = config_path("");
     conf_dir.push(file_name);
-    if conf_dir.exists() {
-        let mut file_conf = File::open(conf_dir).unwrap();
-        A::load_from(&manager, &mut file_conf).unwrap()
-    } else {
-        A::default()
+    if !conf_dir.exists() {
+        return Err(SettingsError::Missing);
     }
+    let content = std::fs::read(conf_dir)?;
+    A::load_from(&manager, &mut content.as_slice()).map_err(|e| match e {
+        PreferencesError::Json(e) => SettingsError::Parse(e.to_string()),
+        // the content is already in memory, any other error comes from the decryption
+        e => SettingsError::Decrypt(e.to_string()),
+    })
 }
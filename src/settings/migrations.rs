@@ -0,0 +1,338 @@
+use log::info;
+use serde_json::Value;
+
+use super::{accounts::secret_key, new_account_id, offline_uuid, SettingsError};
+
+/// Version of the settings layout written by this build
+pub const SCHEMA_VERSION: u32 = 6;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Each migration upgrades the payload from the version of its index to the next one
+static MIGRATIONS: &[Migration] = &[
+    v0_typed_origin,
+    v1_instance_manifests,
+    v2_account_ids,
+    v3_secret_refs,
+    v4_offline_uuids,
+    v5_last_launched_name,
+];
+
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32
+}
+
+/// Upgrade step by step an old payload until the current version,
+/// returns true if the payload was changed
+pub fn migrate(value: &mut Value) -> Result<bool, SettingsError> {
+    let from = schema_version(value);
+    if from > SCHEMA_VERSION {
+        return Err(SettingsError::Parse(format!(
+            "The settings were saved with a newer launcher (schema v{from})"
+        )));
+    }
+    if !value.is_object() {
+        return Err(SettingsError::Parse(
+            "The settings are not an object".to_string(),
+        ));
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        let next = version as u32 + 1;
+        migration(value)
+            .map_err(|e| SettingsError::Parse(format!("Migration to v{next} failed: {e}")))?;
+        value["schema_version"] = Value::from(next);
+        info!("Settings migrated from v{version} to v{next}");
+    }
+    Ok(from != SCHEMA_VERSION)
+}
+
+/// v0 stored the origin of the session as a free string
+fn v0_typed_origin(value: &mut Value) -> Result<(), String> {
+    let Some(session) = value.get_mut("session").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+    let origin = match session.get("origin").and_then(Value::as_str) {
+        Some("MICROSOFT") => "Microsoft",
+        Some("MOJANG") => "Mojang",
+        Some("MINECRAFT") => "Minecraft",
+        _ => "Local",
+    };
+    session.insert("origin".to_string(), Value::from(origin));
+    Ok(())
+}
+
+/// v1 stored all the instances inside the settings file, they wait on `legacy_instances`
+/// until `load_instances` writes the manifest into their folder
+fn v1_instance_manifests(value: &mut Value) -> Result<(), String> {
+    let Some(root) = value.as_object_mut() else {
+        return Ok(());
+    };
+    if let Some(instances) = root.remove("instances") {
+        root.insert("legacy_instances".to_string(), instances);
+    }
+    Ok(())
+}
+
+/// v2 had a single session without id
+fn v2_account_ids(value: &mut Value) -> Result<(), String> {
+    let Some(session) = value.get_mut("session").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+    if !session.contains_key("id") {
+        session.insert("id".to_string(), Value::from(new_account_id()));
+    }
+    Ok(())
+}
+
+/// v3 stored the tokens of the accounts on the settings, they wait on `legacy_secrets`
+/// until the secret store is unlocked
+fn v3_secret_refs(value: &mut Value) -> Result<(), String> {
+    let Some(root) = value.as_object_mut() else {
+        return Ok(());
+    };
+    let mut legacy = serde_json::Map::new();
+    if let Some(session) = root.get_mut("session") {
+        move_tokens(session, &mut legacy);
+    }
+    if let Some(accounts) = root.get_mut("accounts").and_then(Value::as_array_mut) {
+        accounts
+            .iter_mut()
+            .for_each(|session| move_tokens(session, &mut legacy));
+    }
+    root.insert("legacy_secrets".to_string(), Value::Object(legacy));
+    Ok(())
+}
+
+fn move_tokens(session: &mut Value, legacy: &mut serde_json::Map<String, Value>) {
+    let Some(session) = session.as_object_mut() else {
+        return;
+    };
+    let mut take = |key: &str| {
+        session
+            .remove(key)
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default()
+    };
+    let (access_token, refresh_token) = (take("access_token"), take("refresh_token"));
+    if session.get("origin").and_then(Value::as_str) == Some("Local") {
+        return;
+    }
+    let Some(id) = session.get("id").and_then(Value::as_str).map(String::from) else {
+        return;
+    };
+    legacy.insert(
+        id.clone(),
+        serde_json::json!({
+            "access_token": access_token,
+            "refresh_token": refresh_token,
+        }),
+    );
+    session.insert("secret".to_string(), Value::from(secret_key(&id)));
+}
+
+/// v4 launched the local accounts with the uuid `null`
+fn v4_offline_uuids(value: &mut Value) -> Result<(), String> {
+    let Some(root) = value.as_object_mut() else {
+        return Ok(());
+    };
+    if let Some(session) = root.get_mut("session") {
+        set_offline_uuid(session);
+    }
+    if let Some(accounts) = root.get_mut("accounts").and_then(Value::as_array_mut) {
+        accounts.iter_mut().for_each(set_offline_uuid);
+    }
+    Ok(())
+}
+
+fn set_offline_uuid(session: &mut Value) {
+    let Some(session) = session.as_object_mut() else {
+        return;
+    };
+    let local = session.get("origin").and_then(Value::as_str) == Some("Local");
+    let without_uuid = session
+        .get("uuid")
+        .and_then(Value::as_str)
+        .unwrap_or("null")
+        == "null";
+    if !local || !without_uuid {
+        return;
+    }
+    let name = session
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    session.insert("uuid".to_string(), Value::from(offline_uuid(&name)));
+}
+
+/// v5 kept a copy of the whole last instance, it gets old when the instance changes
+fn v5_last_launched_name(value: &mut Value) -> Result<(), String> {
+    let Some(last) = value.get_mut("last_launched") else {
+        return Ok(());
+    };
+    *last = last
+        .get("name")
+        .and_then(Value::as_str)
+        .map(Value::from)
+        .unwrap_or(Value::Null);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{migrate, schema_version, SCHEMA_VERSION};
+    use crate::settings::{offline_uuid, AccountOrigin, LauncherSettings};
+
+    macro_rules! fixture {
+        ($version:literal) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/settings/v",
+                $version,
+                ".json"
+            ))
+        };
+    }
+
+    /// Run all the migrations over the fixture and parse the result
+    fn load(fixture: &str) -> (Value, LauncherSettings) {
+        let mut value = serde_json::from_str::<Value>(fixture).unwrap();
+        let from = schema_version(&value);
+        let migrated = migrate(&mut value).unwrap();
+        assert_eq!(migrated, from != SCHEMA_VERSION);
+        assert_eq!(schema_version(&value), SCHEMA_VERSION);
+        let cfg = serde_json::from_value::<LauncherSettings>(value.clone()).unwrap();
+        (value, cfg)
+    }
+
+    #[test]
+    fn every_version_has_a_fixture() {
+        let fixtures = [
+            fixture!("0"),
+            fixture!("1"),
+            fixture!("2"),
+            fixture!("3"),
+            fixture!("4"),
+            fixture!("5"),
+        ];
+        assert_eq!(fixtures.len(), SCHEMA_VERSION as usize);
+        for (version, fixture) in fixtures.iter().enumerate() {
+            let value = serde_json::from_str::<Value>(fixture).unwrap();
+            assert_eq!(schema_version(&value), version as u32);
+            load(fixture);
+        }
+    }
+
+    #[test]
+    fn from_v0() {
+        let (value, cfg) = load(fixture!("0"));
+        assert!(value.get("instances").is_none());
+        assert!(value.pointer("/session/access_token").is_none());
+        assert_eq!(cfg.session.origin, AccountOrigin::Microsoft);
+        assert!(!cfg.session.id.is_empty());
+        let secret = cfg.legacy_secrets.get(&cfg.session.id).unwrap();
+        assert_eq!(secret.access_token, "v0-access");
+        assert_eq!(
+            cfg.session.secret.as_deref(),
+            Some(format!("account-{}", cfg.session.id).as_str())
+        );
+        assert_eq!(cfg.session.uuid, "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(cfg.last_launched.as_deref(), Some("Survival"));
+        assert_eq!(cfg.legacy_instances[0].name, "Survival");
+    }
+
+    #[test]
+    fn from_v1() {
+        let (value, cfg) = load(fixture!("1"));
+        assert!(value.get("instances").is_none());
+        // the migration not touches the disk, the instance waits for `load_instances`
+        let instance = &cfg.legacy_instances[0];
+        assert_eq!(instance.name, "Creative");
+        assert_eq!(instance.java_path, "/usr/bin/java");
+        assert_eq!(cfg.session.origin, AccountOrigin::Local);
+        assert_eq!(cfg.session.uuid, offline_uuid("Alex"));
+        assert!(cfg.session.secret.is_none());
+        assert!(cfg.legacy_secrets.is_empty());
+    }
+
+    #[test]
+    fn from_v2() {
+        let (_, cfg) = load(fixture!("2"));
+        assert!(!cfg.session.id.is_empty());
+        let secret = cfg.legacy_secrets.get(&cfg.session.id).unwrap();
+        assert_eq!(secret.access_token, "v2-access");
+        assert_eq!(secret.refresh_token, "v2-refresh");
+        assert_eq!(cfg.launch_defaults.max_memory, Some(4096));
+    }
+
+    #[test]
+    fn from_v3() {
+        let (value, cfg) = load(fixture!("3"));
+        assert!(value.pointer("/session/refresh_token").is_none());
+        assert_eq!(cfg.session.id, "186f5a1c2b3d4e50");
+        assert_eq!(cfg.session.expires_at, Some(1680000000));
+        assert_eq!(
+            cfg.legacy_secrets["186f5a1c2b3d4e50"].refresh_token,
+            "v3-refresh"
+        );
+        // the local accounts have no secret
+        let local = &cfg.accounts[0];
+        assert!(local.secret.is_none());
+        assert!(!cfg.legacy_secrets.contains_key(&local.id));
+        assert_eq!(local.uuid, offline_uuid("Alex"));
+    }
+
+    #[test]
+    fn legacy_secrets_are_saved_until_unlock() {
+        let (_, cfg) = load(fixture!("3"));
+        // the migrated settings are saved before the store is unlocked
+        let saved = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(
+            saved.pointer("/legacy_secrets/186f5a1c2b3d4e50/access_token"),
+            Some(&Value::from("v3-access"))
+        );
+        let cfg = serde_json::from_value::<LauncherSettings>(saved).unwrap();
+        assert_eq!(
+            cfg.legacy_secrets["186f5a1c2b3d4e50"].refresh_token,
+            "v3-refresh"
+        );
+    }
+
+    #[test]
+    fn from_v4() {
+        let (_, cfg) = load(fixture!("4"));
+        assert_eq!(cfg.session.uuid, offline_uuid("Alex"));
+        // the uuid of the remote accounts is kept
+        assert_eq!(cfg.accounts[0].uuid, "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(
+            cfg.legacy_secrets["186f5a1c2b3d4e50"].access_token,
+            "v4-access"
+        );
+    }
+
+    #[test]
+    fn from_v5() {
+        let (value, cfg) = load(fixture!("5"));
+        assert_eq!(value["last_launched"], Value::from("Creative"));
+        assert_eq!(cfg.last_launched.as_deref(), Some("Creative"));
+        assert_eq!(cfg.session.name, "Alex");
+    }
+
+    #[test]
+    fn current_version_is_not_migrated() {
+        let mut value = serde_json::to_value(LauncherSettings::default()).unwrap();
+        assert!(!migrate(&mut value).unwrap());
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut value = serde_json::json!({ "schema_version": SCHEMA_VERSION + 1 });
+        assert!(migrate(&mut value).is_err());
+    }
+}
@@ -1,11 +1,18 @@
-use std::fmt::Debug;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    sync::Arc,
+};
 
 use crate::{
-    args::{OpenMCArgs, OpenMCommands},
-    data::{config_path, data_path, theme::ThemeType},
+    args::OpenMCArgs,
+    auth::AuthEndpoints,
+    data::{config_path, data_path, theme::ThemeType, DEFAULT_INSTANCE_ICON},
+    java::{find_runtime, required_java},
+    secrets::{SecretBackend, SecretStore},
 };
 use clap::Parser;
-use log::{debug, info, trace};
+use log::{debug, error, info, trace, warn};
 use mc_downloader::launcher_manifest::LauncherManifestVersion;
 use serde::{Deserialize, Serialize};
 
@@ -14,22 +21,74 @@ use egui::Color32;
 #[cfg(feature = "inspect")]
 use egui_inspect::EguiInspect;
 
+mod accounts;
+mod error;
+mod instances;
 mod load;
+mod migrations;
 mod save;
 
+pub use accounts::{new_account_id, offline_uuid, SessionSecret};
+pub use error::SettingsError;
+pub use instances::*;
 pub use load::load_settings;
+pub use migrations::SCHEMA_VERSION;
 pub use save::save_settings;
 
+pub static SETTINGS_FILE: &str = "launcher.conf";
+
 #[derive(Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "inspect", derive(EguiInspect))]
 pub struct UserSession {
+    #[serde(default = "new_account_id")]
+    #[cfg_attr(feature = "inspect", inspect(no_edit))]
+    pub id: String,
     pub name: String,
     #[serde(default)]
     pub face_img: String,
     pub uuid: String,
+    /// The tokens live on the secret store, never on the settings file
+    #[serde(skip, default = "null_token")]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
     pub access_token: String,
+    /// Token of Microsoft used to renew the `access_token`
+    #[serde(skip)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub refresh_token: String,
+    /// Key of the tokens on the secret store
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub secret: Option<String>,
+    /// Unix time when the `access_token` expires, `None` when is unknown
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub expires_at: Option<u64>,
     #[cfg_attr(feature = "inspect", inspect(hide))]
-    pub origin: String,
+    pub origin: AccountOrigin,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountOrigin {
+    #[default]
+    Local,
+    Microsoft,
+    Mojang,
+    Minecraft,
+}
+
+impl Display for AccountOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountOrigin::Local => write!(f, "LOCAL"),
+            AccountOrigin::Microsoft => write!(f, "MICROSOFT"),
+            AccountOrigin::Mojang => write!(f, "MOJANG"),
+            AccountOrigin::Minecraft => write!(f, "MINECRAFT"),
+        }
+    }
+}
+
+fn null_token() -> String {
+    "null".to_string()
 }
 
 impl Default for UserSession {
@@ -54,11 +113,15 @@ impl Default for UserSession {
                 .join(" ")
         };
         Self {
+            id: new_account_id(),
+            uuid: offline_uuid(&name),
             name,
-            origin: "LOCAL".to_string(),
+            origin: AccountOrigin::Local,
             face_img: String::new(),
-            uuid: d.to_string(),
             access_token: d.to_string(),
+            refresh_token: String::new(),
+            secret: None,
+            expires_at: None,
         }
     }
 }
@@ -67,37 +130,103 @@ impl UserSession {
     pub fn is_logged(&self) -> bool {
         let d = "null";
         // check local
-        self.origin == *"LOCAL" && !self.name.is_empty()
-            || self.origin.is_empty() && !self.name.is_empty()
+        self.origin == AccountOrigin::Local && !self.name.is_empty()
             // check remote
             || !self.name.is_empty()
                 && self.uuid != *d
                 && self.access_token != *d
     }
 
-    pub fn account_origin(&self) -> String {
-        if self.origin.is_empty() || !self.is_logged() {
-            return String::from("LOCAL");
+    pub fn account_origin(&self) -> AccountOrigin {
+        if !self.is_logged() {
+            return AccountOrigin::Local;
         }
-        self.origin.clone()
+        self.origin
     }
 }
 
 // Data to save and load into preferences
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "inspect", derive(EguiInspect))]
 pub struct LauncherSettings {
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(no_edit))]
+    pub schema_version: u32,
     pub theme: ThemeType,
+    /// The active account
     #[serde(default)]
     pub session: UserSession,
+    /// The other saved accounts
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub accounts: Vec<UserSession>,
+    /// Name of the instance selected on the home, resolved with `last_instance`
     #[cfg_attr(
         feature = "inspect",
         inspect(hide, custom_func_mut = "custom_instance_inspect")
     )]
-    pub last_launched: Option<LauncherInstance>,
+    pub last_launched: Option<String>,
+    /// Each instance is stored on its own `instance.toml`
+    #[serde(skip)]
     pub instances: Vec<LauncherInstance>,
+    /// Launch options inherited by all instances
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub launch_defaults: LaunchOptions,
     #[serde(skip)]
     pub exists_assets: bool,
+    /// Services used by the Microsoft login, taken from the build and the command line
+    #[serde(skip)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub auth: AuthEndpoints,
+    /// Backend that has the tokens now
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    secret_backend: SecretBackend,
+    /// Backend chosen by the user that is not unlocked yet
+    #[serde(skip)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    next_backend: Option<SecretBackend>,
+    /// Tokens saved by the settings before v4, only written by the migration.
+    /// They stay on the file until the secret store has them, so a skipped
+    /// unlock not loses them
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    legacy_secrets: HashMap<String, SessionSecret>,
+    /// Instances saved by the settings before v2, only written by the migration.
+    /// They stay on the file until `load_instances` writes their manifest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    legacy_instances: Vec<LauncherInstance>,
+    #[serde(skip)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    secrets: Option<Arc<dyn SecretStore>>,
+    /// Store of the previous backend, cleaned after the tokens are moved
+    #[serde(skip)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    retired_secrets: Option<Arc<dyn SecretStore>>,
+}
+
+impl Default for LauncherSettings {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            theme: Default::default(),
+            session: Default::default(),
+            accounts: Vec::new(),
+            last_launched: None,
+            instances: Vec::new(),
+            launch_defaults: LaunchOptions::default(),
+            exists_assets: false,
+            auth: AuthEndpoints::default(),
+            secret_backend: SecretBackend::default(),
+            next_backend: None,
+            legacy_secrets: HashMap::new(),
+            legacy_instances: Vec::new(),
+            secrets: None,
+            retired_secrets: None,
+        }
+    }
 }
 
 // Data of instance
@@ -118,6 +247,102 @@ pub struct LauncherInstance {
     pub downloaded: bool,
     #[serde(skip)]
     pub downloading: bool,
+    /// The folder of the instance not exists, only the legacy instances can miss it
+    #[serde(skip)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub missing: bool,
+    /// Used to search and group the instances
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub options: LaunchOptions,
+    /// Unix timestamp in seconds of the last launch
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub last_played: Option<u64>,
+    /// Seconds played between all the launches
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub total_playtime: u64,
+    /// Id of the account used to launch the instance instead of the active one
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub account: Option<String>,
+    /// The last launches, the oldest first
+    #[serde(default)]
+    #[cfg_attr(feature = "inspect", inspect(hide))]
+    pub history: Vec<LaunchRecord>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LaunchRecord {
+    /// Unix timestamp in seconds
+    pub started: u64,
+    /// Seconds that the game was open
+    pub duration: u64,
+    pub exit_code: Option<i32>,
+}
+
+/// Options used to launch an instance, `None` inherits the global defaults
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LaunchOptions {
+    /// Min heap of the JVM in megabytes
+    #[serde(default)]
+    pub min_memory: Option<u32>,
+    /// Max heap of the JVM in megabytes
+    #[serde(default)]
+    pub max_memory: Option<u32>,
+    /// Extra flags passed to the JVM
+    #[serde(default)]
+    pub jvm_args: Option<String>,
+    /// Extra arguments passed to the game
+    #[serde(default)]
+    pub game_args: Option<String>,
+    /// Width and height of the game window
+    #[serde(default)]
+    pub resolution: Option<(u32, u32)>,
+    #[serde(default)]
+    pub fullscreen: Option<bool>,
+    /// Environment variables of the game process
+    #[serde(default)]
+    pub env: Option<Vec<(String, String)>>,
+}
+
+impl LaunchOptions {
+    /// The values of `self` win over the values of `defaults`
+    pub fn inherit(&self, defaults: &LaunchOptions) -> LaunchOptions {
+        LaunchOptions {
+            min_memory: self.min_memory.or(defaults.min_memory),
+            max_memory: self.max_memory.or(defaults.max_memory),
+            jvm_args: self.jvm_args.clone().or_else(|| defaults.jvm_args.clone()),
+            game_args: self
+                .game_args
+                .clone()
+                .or_else(|| defaults.game_args.clone()),
+            resolution: self.resolution.or(defaults.resolution),
+            fullscreen: self.fullscreen.or(defaults.fullscreen),
+            env: self.env.clone().or_else(|| defaults.env.clone()),
+        }
+    }
+
+    /// Check the values that the JVM or the game refuses, call it over the inherited options
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(min), Some(max)) = (self.min_memory, self.max_memory) {
+            if min > 0 && max > 0 && min > max {
+                return Err(format!(
+                    "The min memory ({min}MB) cannot be greater than the max memory ({max}MB)"
+                ));
+            }
+        }
+        if let Some((width, height)) = self.resolution {
+            if width == 0 || height == 0 {
+                return Err("The resolution cannot be zero".to_string());
+            }
+        }
+        Ok(())
+    }
 }
 
 // Specific version type and literal version
@@ -130,32 +355,94 @@ pub enum MinecraftVersion {
 }
 
 impl LauncherSettings {
-    pub fn new() -> Self {
-        let opts = OpenMCArgs::parse();
-        let mut cfg = load_settings::<Self>("launcher.conf");
-
-        if let Some(cmd) = opts.command {
-            match cmd {
-                OpenMCommands::Launch { instance } => {
-                    // @TODO: launch instance
-                    if let Some(instance) = cfg.instances.iter().find(|i| i.name == instance) {
-                        println!("Launching Instance '{}'", instance.name);
-                        std::process::exit(0);
-                    } else {
-                        println!("Instance '{}' not exists", instance);
-                        std::process::exit(1);
-                    }
+    pub fn new() -> Result<Self, SettingsError> {
+        Ok(Self::load()?.with_args())
+    }
+
+    /// Read the settings file, if not exists yet the default settings are used
+    pub fn load() -> Result<Self, SettingsError> {
+        let mut cfg = match Self::load_from(SETTINGS_FILE) {
+            Ok((cfg, migrated)) => {
+                if migrated {
+                    save_settings(&cfg, SETTINGS_FILE)?;
+                    info!("Settings saved with schema v{SCHEMA_VERSION}");
                 }
+                cfg
             }
+            Err(SettingsError::Missing) => Self::default(),
+            Err(e) => return Err(e),
+        };
+        let pending = cfg.legacy_instances.len();
+        cfg.instances = load_instances(&mut cfg.legacy_instances);
+        if cfg.legacy_instances.len() != pending {
+            cfg.save();
         }
+        Ok(cfg)
+    }
 
-        cfg.check_assets();
+    /// Read the raw payload and upgrade it to the current schema before parse it,
+    /// the flag is true when the payload was migrated
+    fn load_from(file_name: &str) -> Result<(Self, bool), SettingsError> {
+        let mut value = load_settings::<serde_json::Value>(file_name)?;
+        let migrated = migrations::migrate(&mut value)?;
+        let cfg = serde_json::from_value::<Self>(value)
+            .map_err(|e| SettingsError::Parse(e.to_string()))?;
+        Ok((cfg, migrated))
+    }
+
+    /// Apply the options of the command line
+    pub fn with_args(mut self) -> Self {
+        let opts = OpenMCArgs::parse();
+        self.check_assets();
 
         if let Some(t) = opts.theme {
-            cfg.theme = t;
+            self.theme = t;
+        }
+        if let Some(server) = opts.auth_server {
+            self.auth = AuthEndpoints::with_server(&server);
+        }
+        if let Some(client_id) = opts.ms_client_id {
+            self.auth.client_id = client_id;
         }
 
-        cfg
+        self
+    }
+
+    /// Load the last backup and replace the settings file with it
+    pub fn restore_backup() -> Result<Self, SettingsError> {
+        let (mut cfg, _) = Self::load_from(&format!("{SETTINGS_FILE}.bak"))?;
+        cfg.instances = load_instances(&mut cfg.legacy_instances);
+        let cfg = cfg.with_args();
+        // the broken file must not replace the backup on save
+        Self::discard_settings_file()?;
+        save_settings(&cfg, SETTINGS_FILE)?;
+        info!("Settings restored from backup");
+        Ok(cfg)
+    }
+
+    /// Keep the broken settings file as `.broken` and start from the default settings
+    pub fn reset() -> Result<Self, SettingsError> {
+        Self::discard_settings_file()?;
+        let mut cfg = Self::default().with_args();
+        cfg.instances = load_instances(&mut cfg.legacy_instances);
+        save_settings(&cfg, SETTINGS_FILE)?;
+        info!("Settings reset to default");
+        Ok(cfg)
+    }
+
+    fn discard_settings_file() -> Result<(), SettingsError> {
+        let mut path = config_path("");
+        path.push(SETTINGS_FILE);
+        if path.exists() {
+            std::fs::rename(&path, path.with_extension("conf.broken"))?;
+        }
+        Ok(())
+    }
+
+    pub fn has_backup() -> bool {
+        let mut path = config_path("");
+        path.push(format!("{SETTINGS_FILE}.bak"));
+        path.is_file()
     }
 
     pub fn check_assets(&mut self) {
@@ -167,10 +454,16 @@ impl LauncherSettings {
             && p_faces.read_dir().unwrap().count() > 1;
     }
 
-    pub fn allocate_java(&self, _version: &str) -> String {
-        // @TODO: allocate java binary on download java folder
-        // if not exists automatic download and store into java folder
-        todo!();
+    /// Path of the java runtime that fits better with the minecraft version
+    pub fn allocate_java(&self, version: &str) -> Option<String> {
+        let runtime = find_runtime(version);
+        if runtime.is_none() {
+            warn!(
+                "There is no java {} runtime for version {version}",
+                required_java(version)
+            );
+        }
+        runtime.map(|r| r.path)
     }
 
     pub fn add_instance(
@@ -194,15 +487,29 @@ impl LauncherSettings {
             path.push("icon.png");
             path
         };
-        std::fs::copy(icon_path, new_icon_path.clone()).unwrap();
+        if icon_path.is_empty() {
+            std::fs::write(new_icon_path.clone(), DEFAULT_INSTANCE_ICON).unwrap();
+        } else {
+            std::fs::copy(icon_path, new_icon_path.clone()).unwrap();
+        }
         info!("Icon copied Succesfull: {new_icon_path:?}");
+        let java_path = match instance.version.as_ref() {
+            Some(v) if instance.java_path.is_empty() => {
+                self.allocate_java(&v.get_version_id()).unwrap_or_default()
+            }
+            _ => instance.java_path.clone(),
+        };
         let instance = LauncherInstance {
             path: path.to_str().unwrap().to_string(),
+            java_path,
             ..instance
         };
         debug!("New LauncherInstance Information: {instance:?}");
-        if self.instances.is_empty() || self.last_launched.is_none() {
-            self.last_launched = Some(instance.clone());
+        if let Err(e) = instance.save_manifest() {
+            error!("Cannot save manifest of instance '{name}': {e}");
+        }
+        if self.last_instance().is_none() {
+            self.last_launched = Some(instance.name.clone());
         }
         self.instances.push(instance);
         info!("Instance pushed to instances list");
@@ -210,10 +517,88 @@ impl LauncherSettings {
         path
     }
 
+    /// Rules that every instance name must follow
+    pub fn validate_instance_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("The name cannot empty".to_string());
+        }
+        if name.len() < 3 {
+            return Err("The name size need at least 3 characters".to_string());
+        }
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err("The name cannot contain path separators".to_string());
+        }
+        if self.instances.iter().any(|i| i.name == name) {
+            return Err("An instance of this name already exists".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn rename_instance(&mut self, name: &str, new_name: &str) -> Result<(), String> {
+        if name == new_name {
+            return Ok(());
+        }
+        self.validate_instance_name(new_name)?;
+        let Some(instance) = self.instances.iter_mut().find(|i| i.name == name) else {
+            return Err(format!("Instance '{name}' not exists"));
+        };
+
+        let mut new_path = data_path("instances");
+        new_path.push(new_name);
+        // a folder left by another launcher or a removed instance is never overwritten
+        if new_path.exists() {
+            return Err(format!("The folder {new_path:?} already exists"));
+        }
+        std::fs::rename(&instance.path, &new_path).map_err(|e| e.to_string())?;
+        debug!(
+            "Instance folder moved from {} to {new_path:?}",
+            instance.path
+        );
+
+        instance.name = new_name.to_string();
+        instance.path = new_path.to_str().unwrap().to_string();
+        instance.save_manifest().map_err(|e| e.to_string())?;
+        if self.last_launched.as_deref() == Some(name) {
+            self.last_launched = Some(new_name.to_string());
+        }
+        info!("Instance '{name}' renamed to '{new_name}'");
+        self.save();
+        Ok(())
+    }
+
+    /// The instance selected on the home, `None` when it was removed
+    pub fn last_instance(&self) -> Option<&LauncherInstance> {
+        let name = self.last_launched.as_ref()?;
+        self.instances.iter().find(|i| &i.name == name)
+    }
+
+    /// Replace the instance with the same name and persist its manifest
+    pub fn update_instance(&mut self, instance: &LauncherInstance) {
+        if let Some(i) = self.instances.iter_mut().find(|i| i.name == instance.name) {
+            *i = instance.clone();
+        }
+        if let Err(e) = instance.save_manifest() {
+            error!("Cannot save manifest of instance '{}': {e}", instance.name);
+        }
+    }
+
+    /// Add a finished launch to the history of the instance
+    pub fn record_launch(&mut self, name: &str, record: LaunchRecord) {
+        let Some(instance) = self.instances.iter_mut().find(|i| i.name == name) else {
+            warn!("Cannot record the launch of '{name}', the instance not exists");
+            return;
+        };
+        instance.push_launch(record);
+        if let Err(e) = instance.save_manifest() {
+            error!("Cannot save manifest of instance '{name}': {e}");
+        }
+    }
+
     pub fn remove_instance(&mut self, name: String) {
         if let Some(pos) = self.instances.iter().position(|i| i.name == name) {
             self.instances.remove(pos);
-            if self.last_launched.is_some() {
+            self.legacy_instances.retain(|i| i.name != name);
+            if self.last_launched.as_ref() == Some(&name) {
                 self.last_launched = None;
             }
             remove_instance_folder(name.as_str());
@@ -223,9 +608,16 @@ impl LauncherSettings {
 
     pub fn save(&self) -> bool {
         trace!("Save Setting");
-        let b = save_settings::<Self>(self.clone(), "launcher.conf");
-        debug!("Settings Saved: {b}");
-        b
+        match save_settings::<Self>(self, SETTINGS_FILE) {
+            Ok(_) => {
+                debug!("Settings Saved");
+                true
+            }
+            Err(e) => {
+                error!("Cannot save settings: {e}");
+                false
+            }
+        }
     }
 }
 
@@ -311,19 +703,11 @@ impl EguiInspect for MinecraftVersion {
 
 #[allow(unused)]
 #[cfg(feature = "inspect")]
-fn custom_instance_inspect(
-    value: &mut Option<LauncherInstance>,
-    label: &'static str,
-    ui: &mut egui::Ui,
-) {
-    if let Some(v) = value {
-        v.inspect_mut(label, ui);
-    } else {
-        ui.horizontal(|ui| {
-            ui.label(label.to_owned() + ":");
-            ui.label("None");
-        });
-    }
+fn custom_instance_inspect(value: &mut Option<String>, label: &'static str, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label(label.to_owned() + ":");
+        ui.label(value.as_deref().unwrap_or("None"));
+    });
 }
 
 #[allow(unused)]
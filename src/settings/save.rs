@@ -1,13 +1,31 @@
 use std::fs::File;
 
+use log::debug;
 use preferences::security::{SecurePreferences, SecurityManager};
 
 use crate::data::{config_path, OPENMC_SECURE_KEY};
 
-pub fn save_settings<D: SecurePreferences>(data: D, file_name: &str) -> bool {
+use super::SettingsError;
+
+/// Write into a temporal file and replace the old one only when the write finish,
+/// the previous content is keep as `{file_name}.bak`
+pub fn save_settings<D: SecurePreferences>(data: &D, file_name: &str) -> Result<(), SettingsError> {
     let manager = SecurityManager::new(&OPENMC_SECURE_KEY, None);
-    let mut conf_dir = config_path("");
-    conf_dir.push(file_name);
-    let mut file_conf = File::create(conf_dir).unwrap();
-    data.save_to(&manager, &mut file_conf).is_ok()
+    let conf_dir = config_path("");
+    let file_path = conf_dir.join(file_name);
+    let tmp_path = conf_dir.join(format!("{file_name}.tmp"));
+    let bak_path = conf_dir.join(format!("{file_name}.bak"));
+
+    {
+        let mut file_conf = File::create(&tmp_path)?;
+        data.save_to(&manager, &mut file_conf)
+            .map_err(|e| SettingsError::Serialize(e.to_string()))?;
+        file_conf.sync_all()?;
+    }
+    if file_path.exists() {
+        std::fs::copy(&file_path, &bak_path)?;
+        debug!("Backup of settings saved: {bak_path:?}");
+    }
+    std::fs::rename(&tmp_path, &file_path)?;
+    Ok(())
 }
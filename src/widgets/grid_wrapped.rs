@@ -22,6 +22,7 @@ pub struct GridWrapped<T: Clone> {
     button_str: String,
     cell_size: Vec2,
     total_items: Vec<T>,
+    sections: Vec<(usize, String)>,
 }
 
 impl<T> Default for GridWrapped<T>
@@ -35,6 +36,7 @@ where
             show_search: true,
             selected: None,
             total_items: Vec::new(),
+            sections: Vec::new(),
             search_text: String::new(),
             button_str: String::new(),
             cell_size: Vec2::new(20., 20.),
@@ -103,6 +105,12 @@ where
         self
     }
 
+    /// Start index and title of each section, the titles are hidden while searching
+    pub fn set_sections(&mut self, sections: Vec<(usize, String)>) -> &mut Self {
+        self.sections = sections;
+        self
+    }
+
     pub fn reset(&mut self) {
         self.selected = None;
         self.sended = false;
@@ -173,6 +181,16 @@ where
                         }
 
                         for (i, item) in items.iter().enumerate() {
+                            if let Some((_, title)) = self
+                                .sections
+                                .iter()
+                                .find(|(start, _)| *start == i && self.search_text.is_empty())
+                            {
+                                ui.end_row();
+                                ui.add_space(10.);
+                                ui.label(RichText::new(title).size(20.).strong());
+                                ui.end_row();
+                            }
                             let (rect, resp) = ui.allocate_at_least(self.cell_size, Sense::click());
                             let mut rect_margin = rect;
                             rect_margin.max.x += 5.;
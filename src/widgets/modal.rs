@@ -1,5 +1,7 @@
 use eframe::epaint::Shadow;
-use egui::{Align2, Area, Color32, Frame, Id, Key, Layout, Margin, Order, Stroke, Ui, Vec2};
+use egui::{
+    Align2, Area, Color32, Context, Frame, Id, Key, Layout, Margin, Order, Stroke, Ui, Vec2,
+};
 
 pub struct ModalBuilder {
     id: String,
@@ -56,6 +58,11 @@ impl Modal {
         log::info!("Memory Saved!");
     }
 
+    /// Open the modal outside of a `Ui`
+    pub fn open(&self, ctx: &Context) {
+        ctx.memory_mut(|mem| mem.open_popup(self.id));
+    }
+
     pub fn show(&self, ui: &mut Ui, mut content: impl FnMut(&mut Ui)) {
         if ui.memory(|mem| mem.is_popup_open(self.id)) {
             let rect = ui.max_rect();
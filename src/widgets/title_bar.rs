@@ -1,17 +1,14 @@
-use std::sync::{mpsc::Receiver, Arc, Mutex};
-
 use eframe::egui;
 use egui::{Align, Align2, Color32, FontId, Id, Layout, Sense, Stroke};
 use egui_extras::RetainedImage;
-use log::{debug, info};
-use mc_downloader::prelude::DownloaderService;
+use log::{debug, error, info};
 
 use crate::{
     data::{theme::ThemeType, APP_NAME},
-    download_svc::{DownloadProgress, DownloadProgressMessage},
+    download_svc::format_bytes,
     resources::Icons,
+    screens::utils::format_elapsed,
     settings::LauncherSettings,
-    widgets::add_toast,
     MainState,
 };
 
@@ -20,27 +17,12 @@ use super::IconButton;
 pub struct TitleBar {
     resources: Icons,
     face: Option<RetainedImage>,
-    start_download: bool,
-    curr_progress: f32,
-    progress: DownloadProgress,
-    progress_rcv: Receiver<DownloadProgressMessage>,
 }
 
 impl TitleBar {
     pub fn new(cfg: &LauncherSettings) -> Self {
-        let (progress, progress_rcv) = DownloadProgress::new();
-
         Self {
-            progress,
-            progress_rcv,
-            face: if cfg.session.face_img.is_empty() {
-                None
-            } else {
-                let image_bytes = std::fs::read(cfg.session.face_img.clone()).unwrap();
-                Some(RetainedImage::from_image_bytes("face_title_bar", &image_bytes).unwrap())
-            },
-            curr_progress: 0.,
-            start_download: false,
+            face: load_face(&cfg.session.face_img),
             resources: Icons::preload().unwrap(),
         }
     }
@@ -52,7 +34,6 @@ impl TitleBar {
         app_rect: egui::Rect,
         state: &mut MainState,
         cfg: &mut LauncherSettings,
-        downloader: &mut Option<DownloaderService>,
     ) {
         let title_bar_height = 32.0;
         let pb_height = 3.;
@@ -80,7 +61,7 @@ impl TitleBar {
         );
 
         // Paint the title:
-        painter.text(
+        let title_rect = painter.text(
             title_bar_rect.center(),
             Align2::CENTER_CENTER,
             title,
@@ -88,10 +69,34 @@ impl TitleBar {
             ui.style().visuals.text_color(),
         );
 
+        // Offline badge, the versions are from the cache
+        if state.manifest.offline {
+            let galley = painter.layout_no_wrap(
+                "OFFLINE".to_string(),
+                FontId::proportional(12.0),
+                Color32::WHITE,
+            );
+            let badge_rect = Align2::LEFT_CENTER
+                .anchor_rect(egui::Rect::from_min_size(
+                    title_rect.right_center() + egui::vec2(14., 0.),
+                    galley.size(),
+                ))
+                .expand2(egui::vec2(6., 2.));
+            painter.rect_filled(badge_rect, 4., Color32::from_rgb(200, 60, 60));
+            painter.galley(badge_rect.center() - galley.size() / 2., galley);
+            let since = state
+                .manifest
+                .fetched
+                .map(|t| format!("the versions are from {}", format_elapsed(t)))
+                .unwrap_or_else(|| "there are no versions saved".to_string());
+            ui.interact(badge_rect, Id::new("title_bar_offline"), Sense::hover())
+                .on_hover_text(format!(
+                    "Cannot reach the version manifest, {since}.\nThe downloaded instances can be launched"
+                ));
+        }
+
         if state.changed_face {
-            let image_bytes = std::fs::read(cfg.session.face_img.clone()).unwrap();
-            self.face =
-                Some(RetainedImage::from_image_bytes("face_title_bar", &image_bytes).unwrap());
+            self.face = load_face(&cfg.session.face_img);
             state.changed_face = false;
         }
 
@@ -147,45 +152,44 @@ impl TitleBar {
             });
         });
 
-        // bottom progressbar on titlebar
-        if let Some(d) = downloader.clone() {
-            if !self.start_download {
-                info!("Start Download");
-                self.start_download = true;
-                let progress = self.progress.clone();
-                std::thread::spawn(move || {
-                    d.run(Some(Arc::new(Mutex::new(progress)))).unwrap();
-                });
-            }
-        }
-        if self.start_download {
-            while let Ok(msg) = self.progress_rcv.try_recv() {
-                match msg {
-                    DownloadProgressMessage::Setup(_) => add_toast(
-                        &mut state.toasts,
-                        "Extra Assets",
-                        "Downloading extra assets for launcher, ex: icons, sounds, etc",
-                        crate::widgets::OpenMCToastKind::Info,
-                    ),
-                    DownloadProgressMessage::Update(curr, _max) => self.curr_progress = curr as f32,
-                    DownloadProgressMessage::End => {
-                        *downloader = None;
-                        self.start_download = false;
-                        cfg.check_assets();
-                    }
-                }
-                ui.allocate_ui_at_rect(pb_rect, |ui| {
-                    debug!("Painting Download Progress {}", self.curr_progress);
-                    let painter = ui.painter();
-                    let rect = ui.max_rect();
-                    let pos = rect.left_center();
-                    let max_width = rect.right_center().x;
-                    let mut to = pos;
-                    to.x += (self.curr_progress / 1394096.) * max_width;
-                    painter.line_segment([pos, to], Stroke::new(1.5, Color32::LIGHT_BLUE));
-                    ui.ctx().request_repaint();
-                });
-            }
+        // bottom progressbar on titlebar, total of the download queue
+        let totals = state.downloads.totals();
+        if totals.jobs > 0 {
+            let progress = totals.progress();
+            debug!("Painting Download Progress {progress}");
+            let painter = ui.painter();
+            let pos = pb_rect.left_center();
+            let mut to = pos;
+            to.x += progress * pb_rect.width();
+            painter.line_segment([pos, to], Stroke::new(1.5, Color32::LIGHT_BLUE));
+            painter.text(
+                pb_rect.center_top() - egui::vec2(0., 1.),
+                Align2::CENTER_BOTTOM,
+                format!("{:.0}% - {}", progress * 100., format_bytes(totals.bytes)),
+                FontId::proportional(11.0),
+                ui.style().visuals.weak_text_color(),
+            );
+            let mut hover_rect = pb_rect;
+            hover_rect.min.y -= 12.;
+            hover_rect.max.y += 3.;
+            let current = state
+                .downloads
+                .jobs()
+                .iter()
+                .filter(|j| j.status.is_active())
+                .map(|j| {
+                    let current = j.current.clone().unwrap_or_else(|| j.status.to_string());
+                    format!("{}: {current}", j.name())
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            ui.interact(hover_rect, Id::new("title_bar_progress"), Sense::hover())
+                .on_hover_text(format!(
+                    "{current}\n{} of {} files at {}/s",
+                    totals.files_done as usize,
+                    totals.files_total,
+                    format_bytes(totals.speed)
+                ));
         }
     }
 
@@ -226,7 +230,7 @@ impl TitleBar {
                     }
                     ui.vertical(|ui| {
                         ui.label(cfg.session.name.clone());
-                        ui.label(cfg.session.account_origin());
+                        ui.label(cfg.session.account_origin().to_string());
                     });
                     ui.image(self.resources.expand_arrow.id(ui.ctx()), (10., 10.));
                 })
@@ -234,12 +238,44 @@ impl TitleBar {
             let resp = ui
                 .interact(resp.rect, Id::new("__openmc__tabtitle"), Sense::click())
                 .on_hover_cursor(egui::CursorIcon::PointingHand)
-                .on_hover_text_at_pointer("Profile Settings");
+                .on_hover_text_at_pointer("Accounts");
 
+            let popup_id = Id::new("__openmc__profile_popup");
             if resp.clicked() {
                 info!("Profile clicked!");
-                state.modal.open_modal(ui);
+                ui.memory_mut(|m| m.toggle_popup(popup_id));
             }
+            egui::popup_below_widget(ui, popup_id, &resp, |ui| {
+                ui.set_min_width(200.);
+                let mut switch_to = None;
+                for account in cfg.all_accounts() {
+                    let active = account.id == cfg.session.id;
+                    let label = format!("{} ({})", account.name, account.origin);
+                    if ui.selectable_label(active, label).clicked() && !active {
+                        switch_to = Some(account.id.clone());
+                    }
+                }
+                if let Some(id) = switch_to {
+                    cfg.switch_account(&id);
+                    state.changed_face = true;
+                }
+                ui.separator();
+                if ui.button("Manage Accounts").clicked() {
+                    state.modal.open_modal(ui);
+                }
+            });
         });
     }
 }
+
+fn load_face(face_img: &str) -> Option<RetainedImage> {
+    if face_img.is_empty() {
+        return None;
+    }
+    let image_bytes = std::fs::read(face_img)
+        .map_err(|e| error!("Cannot read the face {face_img}: {e}"))
+        .ok()?;
+    RetainedImage::from_image_bytes("face_title_bar", &image_bytes)
+        .map_err(|e| error!("Cannot load the face {face_img}: {e}"))
+        .ok()
+}
@@ -1,10 +1,26 @@
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
 
-use egui::{Color32, Margin, Response, RichText, Ui};
+use egui::{Color32, Context, Id, Margin, Response, RichText, Ui};
 use egui_toast::{Toast, Toasts};
+use once_cell::sync::Lazy;
 
 const OPENMC_TOAST: u32 = 0;
+static TOAST_ACTION_ID: &str = "__openmc__toast_action";
+/// Contents kept of the toasts closed by time
+const MAX_TOASTS: u64 = 64;
 
+static NEXT_TOAST: AtomicU64 = AtomicU64::new(0);
+/// Content of each toast by its id
+static TOASTS: Lazy<Mutex<HashMap<u64, ToastContent>>> = Lazy::new(Mutex::default);
+
+#[derive(Clone, Copy)]
 pub enum OpenMCToastKind {
     Info,
     Warn,
@@ -12,6 +28,20 @@ pub enum OpenMCToastKind {
     Success,
 }
 
+/// Extra button of a toast, the click is read from the main loop with `take_toast_action`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToastAction {
+    OpenCrashReport(String),
+}
+
+#[derive(Clone)]
+struct ToastContent {
+    kind: OpenMCToastKind,
+    title: String,
+    desc: String,
+    action: Option<ToastAction>,
+}
+
 pub fn create_toast() -> Toasts {
     Toasts::new()
         .custom_contents(OPENMC_TOAST, custom_info_toast)
@@ -21,37 +51,103 @@ pub fn create_toast() -> Toasts {
 }
 
 fn custom_info_toast(ui: &mut Ui, toast: &mut Toast) -> Response {
+    let id = toast.text.text().parse::<u64>().ok();
+    let content = id.and_then(|id| TOASTS.lock().unwrap().get(&id).cloned());
     egui::Frame::default()
         .fill(Color32::from_rgb(33, 150, 243))
         .inner_margin(Margin::same(12.0))
         .rounding(4.0)
         .show(ui, |ui| {
-            if let [kind, title, desc] =
-                &toast.text.clone().text().split('|').collect::<Vec<&str>>()[..3]
-            {
-                ui.horizontal(|ui| {
-                    let _kind = OpenMCToastKind::from(*kind);
-                    ui.vertical(|ui| {
-                        ui.label(RichText::new(*title).color(Color32::WHITE));
-                        ui.label(RichText::new(*desc).color(Color32::WHITE));
-                    });
-                    if ui.button("Close me").clicked() {
-                        toast.close();
-                    }
+            let Some(content) = content else {
+                toast.close();
+                return;
+            };
+            let mut close = false;
+            ui.horizontal(|ui| {
+                let _kind = content.kind;
+                ui.vertical(|ui| {
+                    ui.label(RichText::new(&content.title).color(Color32::WHITE));
+                    ui.label(RichText::new(&content.desc).color(Color32::WHITE));
                 });
+                if let Some(action) = content.action {
+                    if ui.button(action.label()).clicked() {
+                        ui.data_mut(|d| d.insert_temp(Id::new(TOAST_ACTION_ID), action));
+                        close = true;
+                    }
+                }
+                if ui.button("Close me").clicked() {
+                    close = true;
+                }
+            });
+            if close {
+                toast.close();
+                TOASTS.lock().unwrap().remove(&id.unwrap_or_default());
             }
         })
         .response
 }
 
-pub fn add_toast(toasts: &mut Toasts, title: &str, desc: &str, kind: OpenMCToastKind) {
+/// Save the content and show a toast that only has its id, so the text of the
+/// title or the description cannot break the toast
+fn push_toast(toasts: &mut Toasts, content: ToastContent, duration: Duration) {
+    let id = NEXT_TOAST.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut contents = TOASTS.lock().unwrap();
+        // the toasts closed by time are never removed, only the last ones are kept
+        contents.retain(|i, _| i + MAX_TOASTS > id);
+        contents.insert(id, content);
+    }
     toasts.add(Toast {
-        text: format!("{}|{title}|{desc}", kind.to_string()).into(),
+        text: id.to_string().into(),
         kind: egui_toast::ToastKind::Custom(OPENMC_TOAST),
-        options: egui_toast::ToastOptions::with_duration(Some(Duration::from_secs(10))),
+        options: egui_toast::ToastOptions::with_duration(Some(duration)),
     });
 }
 
+pub fn add_toast(toasts: &mut Toasts, title: &str, desc: &str, kind: OpenMCToastKind) {
+    let content = ToastContent {
+        kind,
+        title: title.to_string(),
+        desc: desc.to_string(),
+        action: None,
+    };
+    push_toast(toasts, content, Duration::from_secs(10));
+}
+
+pub fn add_toast_with_action(
+    toasts: &mut Toasts,
+    title: &str,
+    desc: &str,
+    kind: OpenMCToastKind,
+    action: ToastAction,
+) {
+    let content = ToastContent {
+        kind,
+        title: title.to_string(),
+        desc: desc.to_string(),
+        action: Some(action),
+    };
+    push_toast(toasts, content, Duration::from_secs(20));
+}
+
+/// The action clicked on a toast since the last call
+pub fn take_toast_action(ctx: &Context) -> Option<ToastAction> {
+    let id = Id::new(TOAST_ACTION_ID);
+    ctx.data_mut(|d| {
+        let action = d.get_temp::<ToastAction>(id);
+        d.remove::<ToastAction>(id);
+        action
+    })
+}
+
+impl ToastAction {
+    fn label(&self) -> &str {
+        match self {
+            ToastAction::OpenCrashReport(_) => "Open report",
+        }
+    }
+}
+
 impl ToString for OpenMCToastKind {
     fn to_string(&self) -> String {
         match self {